@@ -0,0 +1,6037 @@
+//! Parsing, regression statistics and plotting for the artist streaming dataset.
+
+use csv::{Reader, ReaderBuilder, StringRecord, Writer};
+use plotters::chart::MeshStyle;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much is printed while the analysis runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LogLevel {
+    /// Only the analysis results.
+    Quiet,
+    /// Results along with progress, status, and warning messages.
+    #[default]
+    Normal,
+    /// Everything, plus skipped rows and intermediate sums.
+    Verbose,
+}
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Normal as u8);
+
+/// Sets the level of the messages printed by `info!`, `status!`, and `debug!`.
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether messages of `level` are printed at the current log level.
+pub fn log_enabled(level: LogLevel) -> bool {
+    LOG_LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Prints a status message to stdout, unless the log level is `Quiet`.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log_enabled($crate::LogLevel::Normal) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Prints a progress or warning message to stderr, unless the log level is `Quiet`.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::log_enabled($crate::LogLevel::Normal) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Prints a diagnostic message to stderr when the log level is `Verbose`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log_enabled($crate::LogLevel::Verbose) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArtistData {
+    pub name: String,
+    pub total_streams: f64,
+    pub solo_streams: f64,
+    pub feature_streams: f64,
+    pub lead_streams: f64,
+    /// Value of the grouping column, when one is configured and the cell is not empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Input file the record was read from, when several files are merged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+/// Records parsed from a dataset along with the number of rows dropped as malformed.
+#[derive(Debug)]
+pub struct ParsedData {
+    pub records: Vec<ArtistData>,
+    pub skipped: usize,
+    /// Rows with a negative stream value, whether they were kept, clamped, or rejected.
+    pub negative_rows: usize,
+    /// Number of empty cells in the total, solo, feature, and lead columns.
+    pub missing: [usize; 4],
+}
+
+/// What to do with a record whose stream value cell is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingPolicy {
+    /// Drop the record, as with any other malformed row.
+    #[default]
+    Skip,
+    /// Fill the cell with the mean of the values present in its column.
+    Impute,
+}
+
+impl std::str::FromStr for MissingPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(MissingPolicy::Skip),
+            "impute" => Ok(MissingPolicy::Impute),
+            _ => Err(format!(
+                "unknown missing value policy '{}', expected skip or impute",
+                s
+            )),
+        }
+    }
+}
+
+/// Which of the total, solo, feature, and lead cells of `record` are absent or empty.
+pub fn missing_fields(record: &StringRecord, columns: &ResolvedColumns) -> [bool; 4] {
+    columns.values.map(|index| {
+        record
+            .get(index)
+            .is_none_or(|field| field.trim().is_empty())
+    })
+}
+
+/// Replaces the NaN placeholders `parse_record` leaves for missing cells with the mean of the
+/// values present in the same column, or 0.0 if the column has none.
+fn impute_column_means(records: &mut [ArtistData]) {
+    let fields: [fn(&mut ArtistData) -> &mut f64; 4] = [
+        |d| &mut d.total_streams,
+        |d| &mut d.solo_streams,
+        |d| &mut d.feature_streams,
+        |d| &mut d.lead_streams,
+    ];
+    for field in fields {
+        let present: Vec<f64> = records
+            .iter_mut()
+            .map(|d| *field(d))
+            .filter(|v| !v.is_nan())
+            .collect();
+        let mean = if present.is_empty() {
+            0.0
+        } else {
+            present.iter().sum::<f64>() / present.len() as f64
+        };
+        for d in records.iter_mut() {
+            let value = field(d);
+            if value.is_nan() {
+                *value = mean;
+            }
+        }
+    }
+}
+
+/// What to do with a record containing a negative stream count, which no real artist has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NegativePolicy {
+    /// Keep the values as they are.
+    #[default]
+    Keep,
+    /// Drop the record.
+    Reject,
+    /// Replace negative values with zero.
+    Clamp,
+}
+
+impl std::str::FromStr for NegativePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(NegativePolicy::Keep),
+            "reject" => Ok(NegativePolicy::Reject),
+            "clamp" => Ok(NegativePolicy::Clamp),
+            _ => Err(format!(
+                "unknown negative value policy '{}', expected keep, reject or clamp",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether any stream count of `artist` is negative.
+pub fn has_negative_streams(artist: &ArtistData) -> bool {
+    [
+        artist.total_streams,
+        artist.solo_streams,
+        artist.feature_streams,
+        artist.lead_streams,
+    ]
+    .iter()
+    .any(|v| *v < 0.0)
+}
+
+/// Applies `policy` to a record, returning `None` if it is rejected.
+fn apply_negative_policy(mut artist: ArtistData, policy: NegativePolicy) -> Option<ArtistData> {
+    if !has_negative_streams(&artist) {
+        return Some(artist);
+    }
+    match policy {
+        NegativePolicy::Keep => {}
+        NegativePolicy::Reject => return None,
+        NegativePolicy::Clamp => {
+            for value in [
+                &mut artist.total_streams,
+                &mut artist.solo_streams,
+                &mut artist.feature_streams,
+                &mut artist.lead_streams,
+            ] {
+                if *value < 0.0 {
+                    *value = 0.0;
+                }
+            }
+        }
+    }
+    Some(artist)
+}
+
+/// How commas in stream values are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommaStyle {
+    /// Commas separate groups of thousands, as in `"57,252.6"`.
+    #[default]
+    Thousands,
+    /// A comma is the decimal separator, as in `"57252,6"`.
+    Decimal,
+}
+
+impl std::str::FromStr for CommaStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "comma-as-thousands" => Ok(CommaStyle::Thousands),
+            "comma-as-decimal" => Ok(CommaStyle::Decimal),
+            _ => Err(format!(
+                "unknown comma style '{}', expected comma-as-thousands or comma-as-decimal",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses a stream count such as `"57,252.6"`, returning `None` if the field is missing or not numeric.
+/// With `CommaStyle::Thousands`, commas are only accepted as thousands separators, so values like
+/// `"1,5"` are rejected. With `CommaStyle::Decimal`, a single comma is read as the decimal point and
+/// any dot is rejected, since in such locales it would be a thousands separator.
+pub fn parse_stream_value(field: Option<&str>, comma: CommaStyle) -> Option<f64> {
+    let field = field?.trim();
+    if comma == CommaStyle::Decimal {
+        if field.contains('.') || field.matches(',').count() > 1 {
+            return None;
+        }
+        return field.replace(',', ".").parse().ok();
+    }
+    let integer_part = field.split('.').next().unwrap_or("");
+    let integer_part = integer_part.strip_prefix('-').unwrap_or(integer_part);
+
+    if integer_part.contains(',') {
+        let mut groups = integer_part.split(',');
+        let first = groups.next().unwrap_or("");
+        let valid_first = (1..=3).contains(&first.len());
+        if !valid_first || groups.any(|group| group.len() != 3) {
+            return None;
+        }
+    }
+
+    field.replace(',', "").parse().ok()
+}
+
+/// Returns a CSV reader builder that tolerates rows with missing or extra fields.
+/// Quoted fields (e.g. `"Tyler, The Creator"`) and a leading UTF-8 BOM are handled by the csv crate.
+pub fn csv_reader_builder() -> ReaderBuilder {
+    let mut builder = ReaderBuilder::new();
+    builder.flexible(true).quoting(true).double_quote(true);
+    builder
+}
+
+/// Identifies a CSV column either by zero-based position or by header name.
+#[derive(Debug, Clone)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl Column {
+    /// Resolves the column to a zero-based index using the CSV header row. An unknown
+    /// name is an error listing the available column names.
+    pub fn resolve(&self, headers: &StringRecord) -> Result<usize, Box<dyn Error>> {
+        match self {
+            Column::Index(index) => Ok(*index),
+            Column::Name(name) => headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .ok_or_else(|| {
+                    let available: Vec<&str> = headers.iter().map(str::trim).collect();
+                    format!(
+                        "column '{}' not found in CSV header; available columns are: {}",
+                        name,
+                        available.join(", ")
+                    )
+                    .into()
+                }),
+        }
+    }
+}
+
+/// Maps each artist field to the CSV column it is read from.
+#[derive(Debug, Clone)]
+pub struct ColumnConfig {
+    pub name: Column,
+    pub total: Column,
+    pub solo: Column,
+    pub feature: Column,
+    pub lead: Column,
+    /// Optional column, such as a genre or year, whose values partition the records.
+    pub group: Option<Column>,
+    /// How commas in the stream value columns are read.
+    pub comma: CommaStyle,
+    /// What to do with records containing negative stream values.
+    pub negatives: NegativePolicy,
+    /// What to do with records with an empty stream value cell.
+    pub missing: MissingPolicy,
+}
+
+impl Default for ColumnConfig {
+    fn default() -> Self {
+        ColumnConfig {
+            name: Column::Index(0),
+            total: Column::Index(1),
+            solo: Column::Index(3),
+            feature: Column::Index(5),
+            lead: Column::Index(4),
+            group: None,
+            comma: CommaStyle::default(),
+            negatives: NegativePolicy::default(),
+            missing: MissingPolicy::default(),
+        }
+    }
+}
+
+impl ColumnConfig {
+    /// Resolves every configured column to an index using the CSV header row, failing if
+    /// the header has too few columns to contain all of them.
+    pub fn resolve(&self, headers: &StringRecord) -> Result<ResolvedColumns, Box<dyn Error>> {
+        let resolved = ResolvedColumns {
+            name: self.name.resolve(headers)?,
+            values: [
+                self.total.resolve(headers)?,
+                self.solo.resolve(headers)?,
+                self.feature.resolve(headers)?,
+                self.lead.resolve(headers)?,
+            ],
+            group: self
+                .group
+                .as_ref()
+                .map(|column| column.resolve(headers))
+                .transpose()?,
+            comma: self.comma,
+            negatives: self.negatives,
+            missing: self.missing,
+        };
+        let required = resolved
+            .values
+            .iter()
+            .chain(&resolved.group)
+            .fold(resolved.name, |a, b| a.max(*b))
+            + 1;
+        if headers.len() < required {
+            return Err(format!(
+                "expected at least {} columns, found {}",
+                required,
+                headers.len()
+            )
+            .into());
+        }
+        Ok(resolved)
+    }
+}
+
+/// Column indices for the artist name, the total, solo, feature, and lead stream values,
+/// and the optional group, along with how the stream values are read and validated.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedColumns {
+    pub name: usize,
+    pub values: [usize; 4],
+    pub group: Option<usize>,
+    pub comma: CommaStyle,
+    pub negatives: NegativePolicy,
+    pub missing: MissingPolicy,
+}
+
+/// Parses a single CSV record. Unparseable stream values are substituted with 0.0,
+/// unless `skip_malformed` is set, in which case `None` is returned for the row.
+/// With `MissingPolicy::Impute`, empty cells are not malformed but left as NaN for
+/// the caller to fill in.
+pub fn parse_record(
+    record: &StringRecord,
+    columns: &ResolvedColumns,
+    skip_malformed: bool,
+) -> Option<ArtistData> {
+    let impute = columns.missing == MissingPolicy::Impute;
+    let missing = missing_fields(record, columns);
+    let values: [Option<f64>; 4] = std::array::from_fn(|i| {
+        if impute && missing[i] {
+            Some(f64::NAN)
+        } else {
+            parse_stream_value(record.get(columns.values[i]), columns.comma)
+        }
+    });
+
+    if skip_malformed && values.iter().any(Option::is_none) {
+        return None;
+    }
+
+    let [total_streams, solo_streams, feature_streams, lead_streams] =
+        values.map(|value| value.unwrap_or(0.0));
+
+    Some(ArtistData {
+        name: record.get(columns.name).unwrap_or("").trim().to_string(),
+        total_streams,
+        solo_streams,
+        feature_streams,
+        lead_streams,
+        group: columns
+            .group
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+            .map(str::to_string),
+        source: None,
+    })
+}
+
+/// Name of the input path that reads the dataset from standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Delimiters considered by `detect_delimiter`, in order of preference on ties.
+pub const CANDIDATE_DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+/// Picks the candidate delimiter occurring most often outside quotes in `line`,
+/// defaulting to a comma when none occurs.
+pub fn detect_delimiter(line: &[u8]) -> u8 {
+    let mut counts = [0usize; CANDIDATE_DELIMITERS.len()];
+    let mut quoted = false;
+    for byte in line {
+        if *byte == b'"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some(i) = CANDIDATE_DELIMITERS.iter().position(|d| d == byte) {
+                counts[i] += 1;
+            }
+        }
+    }
+    let (best, count) =
+        counts.iter().enumerate().fold(
+            (0, 0),
+            |best, (i, c)| if *c > best.1 { (i, *c) } else { best },
+        );
+    if count == 0 {
+        b','
+    } else {
+        CANDIDATE_DELIMITERS[best]
+    }
+}
+
+/// Whether `file_path` names a gzip-compressed file, judged by its `.gz` extension.
+pub fn is_gzip_path(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Opens `file_path`, decompressing it on the fly if it is gzipped.
+#[cfg(feature = "gzip")]
+fn open_file(file_path: &str) -> Result<Box<dyn io::Read>, Box<dyn Error>> {
+    let file = fs::File::open(file_path)?;
+    if is_gzip_path(file_path) {
+        Ok(Box::new(flate2::read::GzDecoder::new(file)))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Opens `file_path`, refusing gzipped files since decompression is not compiled in.
+#[cfg(not(feature = "gzip"))]
+fn open_file(file_path: &str) -> Result<Box<dyn io::Read>, Box<dyn Error>> {
+    if is_gzip_path(file_path) {
+        return Err(format!(
+            "cannot read '{}': gzip input requires building with the 'gzip' feature",
+            file_path
+        )
+        .into());
+    }
+    Ok(Box::new(fs::File::open(file_path)?))
+}
+
+/// Opens a CSV reader over `file_path`, or over standard input when it is `STDIN_PATH`.
+/// Paths ending in `.gz` are decompressed when the `gzip` feature is enabled.
+/// Without an explicit `delimiter`, one is detected from the first line of the input.
+pub fn open_input(
+    file_path: &str,
+    delimiter: Option<u8>,
+) -> Result<Reader<Box<dyn io::Read>>, Box<dyn Error>> {
+    let input: Box<dyn io::Read> = if file_path == STDIN_PATH {
+        Box::new(io::stdin())
+    } else {
+        open_file(file_path)?
+    };
+    let mut input = io::BufReader::with_capacity(64 * 1024, input);
+    let delimiter = match delimiter {
+        Some(delimiter) => delimiter,
+        None => {
+            let buffered = io::BufRead::fill_buf(&mut input)?;
+            let first_line = buffered.split(|b| *b == b'\n').next().unwrap_or_default();
+            let detected = detect_delimiter(first_line);
+            status!(
+                "Auto-detected delimiter '{}'",
+                (detected as char).escape_default()
+            );
+            detected
+        }
+    };
+    Ok(csv_reader_builder()
+        .delimiter(delimiter)
+        .from_reader(Box::new(input)))
+}
+
+/// Parses the artist dataset at `file_path` (or standard input for `-`), whose fields are
+/// separated by `delimiter`, or by a detected delimiter if it is `None`. See
+/// `parse_artist_records`.
+pub fn parse_artist_data(
+    file_path: &str,
+    delimiter: Option<u8>,
+    columns: &ColumnConfig,
+    skip_malformed: bool,
+    progress_every: Option<usize>,
+) -> Result<ParsedData, Box<dyn Error>> {
+    info!("Reading file from path: {}", file_path);
+    parse_artist_records(
+        open_input(file_path, delimiter)?,
+        columns,
+        skip_malformed,
+        progress_every,
+    )
+}
+
+/// Parses several artist datasets with the same column layout, as `parse_artist_data` does
+/// for one, tagging every record with the path it was read from. Returns the records of
+/// each file in order. Fails naming the first file whose header differs from the first
+/// file's header.
+pub fn parse_artist_files(
+    file_paths: &[String],
+    delimiter: Option<u8>,
+    columns: &ColumnConfig,
+    skip_malformed: bool,
+    progress_every: Option<usize>,
+) -> Result<Vec<(String, ParsedData)>, Box<dyn Error>> {
+    let mut expected: Option<(&str, Vec<String>)> = None;
+    let mut files = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        info!("Reading file from path: {}", file_path);
+        let mut reader = open_input(file_path, delimiter)?;
+        let header: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect();
+        match &expected {
+            Some((first_path, first_header)) if *first_header != header => {
+                return Err(format!(
+                    "column layout of '{}' ({}) does not match '{}' ({})",
+                    file_path,
+                    header.join(", "),
+                    first_path,
+                    first_header.join(", ")
+                )
+                .into());
+            }
+            Some(_) => {}
+            None => expected = Some((file_path, header)),
+        }
+
+        let mut parsed = parse_artist_records(reader, columns, skip_malformed, progress_every)
+            .map_err(|e| format!("{}: {}", file_path, e))?;
+        for record in &mut parsed.records {
+            record.source = Some(file_path.clone());
+        }
+        files.push((file_path.clone(), parsed));
+    }
+    Ok(files)
+}
+
+/// A source of artist records, independent of how they are stored.
+pub trait DataSource {
+    /// Loads every record from the source.
+    fn records(&self) -> Result<ParsedData, Box<dyn Error>>;
+}
+
+/// Artist records stored in a delimited text file such as CSV or TSV.
+#[derive(Debug, Clone)]
+pub struct CsvSource {
+    pub path: String,
+    /// Field separator, detected from the first line when `None`.
+    pub delimiter: Option<u8>,
+    pub columns: ColumnConfig,
+    pub skip_malformed: bool,
+    /// Report progress every this many rows, if set.
+    pub progress_every: Option<usize>,
+}
+
+impl DataSource for CsvSource {
+    fn records(&self) -> Result<ParsedData, Box<dyn Error>> {
+        parse_artist_data(
+            &self.path,
+            self.delimiter,
+            &self.columns,
+            self.skip_malformed,
+            self.progress_every,
+        )
+    }
+}
+
+/// Parses artist records from a CSV reader. When `skip_malformed` is set, rows with unparseable
+/// stream values are dropped and counted; otherwise those values are substituted with 0.0.
+/// With `progress_every` set, the number of rows processed so far is printed at that interval.
+/// Records with negative stream values are counted and handled by the configured
+/// `NegativePolicy`, and empty cells are counted per column and handled by the configured
+/// `MissingPolicy`; imputing needs the column means, so it runs once every row is read.
+pub fn parse_artist_records<R: io::Read>(
+    mut reader: Reader<R>,
+    columns: &ColumnConfig,
+    skip_malformed: bool,
+    progress_every: Option<usize>,
+) -> Result<ParsedData, Box<dyn Error>> {
+    let columns = columns.resolve(reader.headers()?)?;
+    let mut data_points = Vec::new();
+    let mut skipped = 0;
+    let mut negative_rows = 0;
+    let mut missing = [0; 4];
+    let mut count_missing = |record: &StringRecord| {
+        for (count, empty) in missing.iter_mut().zip(missing_fields(record, &columns)) {
+            *count += usize::from(empty);
+        }
+    };
+    let mut accept = |artist: ArtistData| {
+        if has_negative_streams(&artist) {
+            negative_rows += 1;
+        }
+        data_points.extend(apply_negative_policy(artist, columns.negatives));
+    };
+
+    #[cfg(feature = "parallel")]
+    let mut rows = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        if let Some(interval) = progress_every.filter(|n| *n > 0) {
+            if row > 0 && row % interval == 0 {
+                status!("Processed {} rows...", row);
+            }
+        }
+        #[cfg(feature = "parallel")]
+        rows.push(record?);
+        #[cfg(not(feature = "parallel"))]
+        {
+            let record = record?;
+            count_missing(&record);
+            match parse_record(&record, &columns, skip_malformed) {
+                Some(artist) => accept(artist),
+                None => {
+                    skipped += 1;
+                    debug_skipped_row(row, &record);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    for (row, (record, parsed)) in rows
+        .iter()
+        .zip(
+            rows.par_iter()
+                .map(|record| parse_record(record, &columns, skip_malformed))
+                .collect::<Vec<_>>(),
+        )
+        .enumerate()
+    {
+        count_missing(record);
+        match parsed {
+            Some(artist) => accept(artist),
+            None => {
+                skipped += 1;
+                debug_skipped_row(row, record);
+            }
+        }
+    }
+
+    if columns.missing == MissingPolicy::Impute {
+        impute_column_means(&mut data_points);
+    }
+    Ok(ParsedData {
+        records: data_points,
+        skipped,
+        negative_rows,
+        missing,
+    })
+}
+
+/// Reports a malformed data row, numbered from the first row after the header, at the
+/// `Verbose` log level.
+fn debug_skipped_row(row: usize, record: &StringRecord) {
+    debug!(
+        "Skipped malformed row {}: {}",
+        row + 1,
+        record.iter().collect::<Vec<_>>().join(" | ")
+    );
+}
+
+/// Returns the artists whose name contains `substring`, ignoring case.
+pub fn filter_by_name<'a>(data: &'a [ArtistData], substring: &str) -> Vec<&'a ArtistData> {
+    let substring = substring.to_lowercase();
+    data.iter()
+        .filter(|d| d.name.to_lowercase().contains(&substring))
+        .collect()
+}
+
+/// Keeps the `n` artists with the most total streams, ordered from most to fewest.
+/// The sort is stable, so artists tied at the cutoff are kept in the order they were read.
+pub fn top_n_by_total(mut data: Vec<ArtistData>, n: usize) -> Vec<ArtistData> {
+    data.sort_by(|a, b| b.total_streams.total_cmp(&a.total_streams));
+    data.truncate(n);
+    data
+}
+
+/// Splits records into four groups of nearly equal size by ascending total streams, from
+/// the smallest artists to the largest.
+pub fn quartiles_by_total(mut data: Vec<ArtistData>) -> Vec<Vec<ArtistData>> {
+    data.sort_by(|a, b| a.total_streams.total_cmp(&b.total_streams));
+    let n = data.len();
+    let mut quartiles = vec![Vec::new(); 4];
+    for (i, artist) in data.into_iter().enumerate() {
+        quartiles[i * 4 / n].push(artist);
+    }
+    quartiles
+}
+
+/// Keeps the artists with at least `min_streams` total streams, in their original order.
+pub fn filter_min_streams(data: Vec<ArtistData>, min_streams: f64) -> Vec<ArtistData> {
+    data.into_iter()
+        .filter(|d| d.total_streams >= min_streams)
+        .collect()
+}
+
+/// Randomly keeps `n` of the records (all of them if there are fewer), drawn without
+/// replacement from an RNG seeded with `seed` so the same seed always gives the same sample.
+/// The kept records stay in their original order.
+pub fn sample_records(data: Vec<ArtistData>, n: usize, seed: u64) -> Vec<ArtistData> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut keep = vec![false; data.len()];
+    for i in rand::seq::index::sample(&mut rng, data.len(), n.min(data.len())) {
+        keep[i] = true;
+    }
+    data.into_iter()
+        .zip(keep)
+        .filter_map(|(artist, keep)| keep.then_some(artist))
+        .collect()
+}
+
+/// How `deduplicate` collapses several records with the same artist name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupStrategy {
+    KeepFirst,
+    KeepLast,
+    /// Averages each stream count over the duplicates.
+    Average,
+}
+
+impl std::str::FromStr for DedupStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(DedupStrategy::KeepFirst),
+            "last" => Ok(DedupStrategy::KeepLast),
+            "average" => Ok(DedupStrategy::Average),
+            _ => Err(format!(
+                "unknown dedup strategy '{}'; valid choices are: first, last, average",
+                s
+            )),
+        }
+    }
+}
+
+/// Name of the group holding records without a group value, which is every record when
+/// no group column is configured.
+pub const ALL_GROUP: &str = "all";
+
+/// Partitions records by their group value, with groups in order of first appearance and
+/// records in their original order within each group.
+pub fn group_by(data: Vec<ArtistData>) -> Vec<(String, Vec<ArtistData>)> {
+    let mut groups: Vec<(String, Vec<ArtistData>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for artist in data {
+        let key = artist
+            .group
+            .clone()
+            .unwrap_or_else(|| ALL_GROUP.to_string());
+        let i = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        groups[i].1.push(artist);
+    }
+    groups
+}
+
+/// Collapses records sharing an artist name into one, according to `strategy`.
+/// Artists keep the position of their first occurrence.
+pub fn deduplicate(data: Vec<ArtistData>, strategy: DedupStrategy) -> Vec<ArtistData> {
+    let mut first_index: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<ArtistData> = Vec::new();
+    let mut counts: Vec<f64> = Vec::new();
+
+    for artist in data {
+        match first_index.get(&artist.name) {
+            Some(&i) => {
+                counts[i] += 1.0;
+                match strategy {
+                    DedupStrategy::KeepFirst => {}
+                    DedupStrategy::KeepLast => unique[i] = artist,
+                    DedupStrategy::Average => {
+                        let kept = &mut unique[i];
+                        kept.total_streams += artist.total_streams;
+                        kept.solo_streams += artist.solo_streams;
+                        kept.feature_streams += artist.feature_streams;
+                        kept.lead_streams += artist.lead_streams;
+                    }
+                }
+            }
+            None => {
+                first_index.insert(artist.name.clone(), unique.len());
+                unique.push(artist);
+                counts.push(1.0);
+            }
+        }
+    }
+
+    if strategy == DedupStrategy::Average {
+        for (artist, count) in unique.iter_mut().zip(counts) {
+            artist.total_streams /= count;
+            artist.solo_streams /= count;
+            artist.feature_streams /= count;
+            artist.lead_streams /= count;
+        }
+    }
+    unique
+}
+
+/// Why a statistic could not be computed from the data it was given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatsError {
+    /// Two slices that must be paired element by element have different lengths.
+    LengthMismatch { expected: usize, found: usize },
+    /// There is no data to compute the statistic from.
+    EmptyInput,
+    /// The x values are all identical, so no line can be fitted.
+    ZeroVariance,
+    /// A weight is negative or NaN.
+    NegativeWeight(f64),
+    /// A percentile lies outside [0, 100].
+    PercentileOutOfRange(f64),
+    /// The statistic needs more points than were given.
+    TooFewPoints { needed: usize, found: usize },
+    /// A moving average window of zero values.
+    ZeroWindow,
+    /// A trimming proportion outside [0, 0.5).
+    InvalidProportion(f64),
+    /// A confidence level outside (0, 1).
+    InvalidLevel(f64),
+    /// The values are all identical, so the shape of their distribution is undefined.
+    ConstantValues,
+    /// Two predictors are so strongly correlated that their coefficients are unstable.
+    Collinear {
+        first: &'static str,
+        second: &'static str,
+        correlation: f64,
+    },
+    /// The normal equations have no unique solution.
+    SingularSystem,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatsError::LengthMismatch { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            StatsError::EmptyInput => write!(f, "no data"),
+            StatsError::ZeroVariance => write!(f, "no variance in x"),
+            StatsError::NegativeWeight(w) => {
+                write!(f, "weights must be non-negative, found {}", w)
+            }
+            StatsError::PercentileOutOfRange(p) => {
+                write!(f, "percentile must be in [0, 100], got {}", p)
+            }
+            StatsError::TooFewPoints { needed, found } => {
+                write!(f, "needs at least {} points, found {}", needed, found)
+            }
+            StatsError::ZeroWindow => write!(f, "moving average window must be at least 1"),
+            StatsError::InvalidProportion(p) => {
+                write!(f, "trimming proportion must be in [0, 0.5), got {}", p)
+            }
+            StatsError::ConstantValues => write!(f, "all values are identical"),
+            StatsError::Collinear {
+                first,
+                second,
+                correlation,
+            } => write!(
+                f,
+                "{} and {} are collinear (r = {:.4}), so their coefficients cannot be separated",
+                first, second, correlation
+            ),
+            StatsError::SingularSystem => write!(f, "predictors are linearly dependent"),
+            StatsError::InvalidLevel(level) => {
+                write!(f, "confidence level must be in (0, 1), got {}", level)
+            }
+        }
+    }
+}
+
+impl Error for StatsError {}
+
+/// Slope and intercept of a fitted regression line.
+pub type LineFit = (f64, f64);
+
+/// Running sums from which a least-squares regression line can be fitted incrementally.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegressionSums {
+    pub n: f64,
+    pub sum_x: f64,
+    pub sum_y: f64,
+    pub sum_xy: f64,
+    pub sum_xx: f64,
+}
+
+impl RegressionSums {
+    /// Adds a single point to the sums.
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.n += 1.0;
+        self.sum_x += x;
+        self.sum_y += y;
+        self.sum_xy += x * y;
+        self.sum_xx += x * x;
+    }
+
+    /// Combines the sums of two disjoint sets of points.
+    #[cfg(any(feature = "parallel", test))]
+    pub fn merge(self, other: RegressionSums) -> RegressionSums {
+        RegressionSums {
+            n: self.n + other.n,
+            sum_x: self.sum_x + other.sum_x,
+            sum_y: self.sum_y + other.sum_y,
+            sum_xy: self.sum_xy + other.sum_xy,
+            sum_xx: self.sum_xx + other.sum_xx,
+        }
+    }
+
+    /// Calculates the slope and intercept of the points added so far.
+    /// Returns `None` when the x values have no variance (including fewer than two points).
+    pub fn fit(&self) -> Option<(f64, f64)> {
+        let denominator = self.n * self.sum_xx - self.sum_x * self.sum_x;
+        if denominator == 0.0 {
+            return None;
+        }
+
+        let slope = (self.n * self.sum_xy - self.sum_x * self.sum_y) / denominator;
+        let intercept = (self.sum_y - slope * self.sum_x) / self.n;
+
+        Some((slope, intercept))
+    }
+}
+
+/// Running means and centered sums of squares and cross-products, updated one point at a
+/// time with Welford's algorithm. Unlike `RegressionSums`, it never subtracts two large
+/// running totals, so it stays accurate when the values are large relative to their spread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OnlineRegression {
+    pub n: usize,
+    pub mean_x: f64,
+    pub mean_y: f64,
+    /// Sum of squared deviations of x from its running mean.
+    pub m2_x: f64,
+    /// Sum of squared deviations of y from its running mean.
+    pub m2_y: f64,
+    /// Sum of the products of the x and y deviations.
+    pub c_xy: f64,
+}
+
+impl OnlineRegression {
+    /// Adds a single point.
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.n += 1;
+        let n = self.n as f64;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        let dy = y - self.mean_y;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    /// Returns the slope and intercept of the points added so far, and their Pearson
+    /// correlation (0.0 when y has no variance). Fails when no points were added or x has
+    /// no variance.
+    pub fn finalize(&self) -> Result<(f64, f64, f64), StatsError> {
+        if self.n == 0 {
+            return Err(StatsError::EmptyInput);
+        }
+        if self.m2_x == 0.0 {
+            return Err(StatsError::ZeroVariance);
+        }
+        let slope = self.c_xy / self.m2_x;
+        let intercept = self.mean_y - slope * self.mean_x;
+        let r = if self.m2_y == 0.0 {
+            0.0
+        } else {
+            (self.c_xy / (self.m2_x * self.m2_y).sqrt()).clamp(-1.0, 1.0)
+        };
+        Ok((slope, intercept, r))
+    }
+}
+
+/// Calculates the linear regression line (slope and intercept).
+/// Fails on empty data or when the x values have no variance (including a single point).
+#[cfg(not(feature = "parallel"))]
+pub fn calculate_regression(data: &[(f64, f64)]) -> Result<LineFit, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let mut sums = RegressionSums::default();
+    for (x, y) in data {
+        sums.push(*x, *y);
+    }
+    sums.fit().ok_or(StatsError::ZeroVariance)
+}
+
+/// Calculates the linear regression line (slope and intercept), reducing the sums in parallel.
+/// Fails on empty data or when the x values have no variance (including a single point).
+#[cfg(feature = "parallel")]
+pub fn calculate_regression(data: &[(f64, f64)]) -> Result<LineFit, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    data.par_iter()
+        .fold(RegressionSums::default, |mut sums, (x, y)| {
+            sums.push(*x, *y);
+            sums
+        })
+        .reduce(RegressionSums::default, RegressionSums::merge)
+        .fit()
+        .ok_or(StatsError::ZeroVariance)
+}
+
+/// Fits the least-squares line through the origin, `y = slope * x`, returning its slope
+/// `sum(xy) / sum(x²)`. Fails on empty data or when every x is zero.
+pub fn calculate_regression_no_intercept(data: &[(f64, f64)]) -> Result<f64, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let sum_xy: f64 = data.iter().map(|(x, y)| x * y).sum();
+    let sum_xx: f64 = data.iter().map(|(x, _)| x * x).sum();
+    if sum_xx == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+    Ok(sum_xy / sum_xx)
+}
+
+/// Fits a weighted least squares line, where each point counts in proportion to its weight.
+/// Returns `(slope, intercept)`, or an error if the weights do not match the data, any
+/// weight is negative, all weights are zero, or the weighted x values have no variance.
+pub fn calculate_weighted_regression(
+    data: &[(f64, f64)],
+    weights: &[f64],
+) -> Result<LineFit, StatsError> {
+    if data.len() != weights.len() {
+        return Err(StatsError::LengthMismatch {
+            expected: data.len(),
+            found: weights.len(),
+        });
+    }
+    if let Some(w) = weights.iter().find(|w| w.is_nan() || **w < 0.0) {
+        return Err(StatsError::NegativeWeight(*w));
+    }
+
+    let total_weight: f64 = weights.iter().sum();
+    if total_weight <= 0.0 {
+        return Err(StatsError::EmptyInput);
+    }
+    let weighted_mean = |f: fn(&(f64, f64)) -> f64| {
+        data.iter().zip(weights).map(|(p, w)| w * f(p)).sum::<f64>() / total_weight
+    };
+    let mean_x = weighted_mean(|(x, _)| *x);
+    let mean_y = weighted_mean(|(_, y)| *y);
+
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for ((x, y), w) in data.iter().zip(weights) {
+        sxy += w * (x - mean_x) * (y - mean_y);
+        sxx += w * (x - mean_x).powi(2);
+    }
+    if sxx == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+    let slope = sxy / sxx;
+    Ok((slope, mean_y - slope * mean_x))
+}
+
+/// Fits the regression line of every relationship in a single pass, reading records one at
+/// a time so the dataset is never held in memory. Malformed rows, and rows with missing
+/// values since they cannot be imputed in one pass, are skipped. The fits are returned in
+/// the order of `relationships`.
+pub fn regress_streaming<R: io::Read>(
+    mut reader: Reader<R>,
+    columns: &ColumnConfig,
+    relationships: &[Relationship],
+) -> Result<Vec<Option<LineFit>>, Box<dyn Error>> {
+    let columns = ResolvedColumns {
+        missing: MissingPolicy::Skip,
+        ..columns.resolve(reader.headers()?)?
+    };
+    let mut fits = vec![OnlineRegression::default(); relationships.len()];
+    let mut record = StringRecord::new();
+
+    while reader.read_record(&mut record)? {
+        let artist = parse_record(&record, &columns, true)
+            .and_then(|artist| apply_negative_policy(artist, columns.negatives));
+        if let Some(artist) = artist {
+            for (fit, relationship) in fits.iter_mut().zip(relationships) {
+                let (x, y) = (relationship.extract)(&artist);
+                fit.push(x, y);
+            }
+        }
+    }
+
+    Ok(fits
+        .iter()
+        .map(|fit| {
+            fit.finalize()
+                .ok()
+                .map(|(slope, intercept, _)| (slope, intercept))
+        })
+        .collect())
+}
+
+/// Predicts y for `x` on the line `y = slope * x + intercept`.
+pub fn predict(slope: f64, intercept: f64, x: f64) -> f64 {
+    slope * x + intercept
+}
+
+/// Calculates Cook's distance of every point of a fitted simple regression, measuring how
+/// much the fit would change if that point were left out. Fails with fewer than three
+/// points or when the x values have no variance.
+pub fn cooks_distance(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<Vec<f64>, StatsError> {
+    let n = data.len();
+    if n < 3 {
+        return Err(StatsError::TooFewPoints {
+            needed: 3,
+            found: n,
+        });
+    }
+    let mean_x = data.iter().map(|(x, _)| *x).sum::<f64>() / n as f64;
+    let sxx: f64 = data.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+
+    let residuals = compute_residuals(data, slope, intercept)?;
+    let mse = residuals.iter().map(|r| r * r).sum::<f64>() / (n - 2) as f64;
+    if mse == 0.0 {
+        return Ok(vec![0.0; n]);
+    }
+
+    Ok(data
+        .iter()
+        .zip(&residuals)
+        .map(|((x, _), residual)| {
+            let leverage = 1.0 / n as f64 + (x - mean_x).powi(2) / sxx;
+            residual.powi(2) / (2.0 * mse) * leverage / (1.0 - leverage).powi(2)
+        })
+        .collect())
+}
+
+/// Default number of point pairs whose slopes `theil_sen` takes the median of.
+pub const THEIL_SEN_PAIRS: usize = 100_000;
+
+/// Fits a Theil-Sen line: the slope is the median of the slopes between pairs of points
+/// and the intercept the median of `y - slope * x`, so a few extreme artists cannot drag
+/// the line around. When there are more than `max_pairs` pairs, an evenly spaced subset of
+/// them is used instead. Fails on empty data or when no pair of points differs in x.
+pub fn theil_sen(data: &[(f64, f64)], max_pairs: usize) -> Result<LineFit, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let n = data.len();
+    let total_pairs = n * n.saturating_sub(1) / 2;
+    let step = total_pairs.div_ceil(max_pairs.max(1)).max(1);
+
+    let mut slopes = Vec::new();
+    let mut pair = 0;
+    let mut next = 0;
+    for i in 0..n {
+        let row_end = pair + (n - 1 - i);
+        while next < row_end {
+            let (x1, y1) = data[i];
+            let (x2, y2) = data[i + 1 + (next - pair)];
+            if x1 != x2 {
+                slopes.push((y2 - y1) / (x2 - x1));
+            }
+            next += step;
+        }
+        pair = row_end;
+    }
+    if slopes.is_empty() {
+        return Err(StatsError::ZeroVariance);
+    }
+
+    slopes.sort_by(f64::total_cmp);
+    let slope = quantile_sorted(&slopes, 0.5);
+    let mut offsets: Vec<f64> = data.iter().map(|(x, y)| y - slope * x).collect();
+    offsets.sort_by(f64::total_cmp);
+    Ok((slope, quantile_sorted(&offsets, 0.5)))
+}
+
+/// Replaces each y with ln(y) so that exponential growth can be fitted by a straight line.
+/// Points with non-positive y are dropped; returns the transformed points and the number
+/// of points dropped.
+pub fn log_transform_y(data: &[(f64, f64)]) -> (Vec<(f64, f64)>, usize) {
+    let transformed: Vec<(f64, f64)> = data
+        .iter()
+        .filter(|(_, y)| *y > 0.0)
+        .map(|(x, y)| (*x, y.ln()))
+        .collect();
+    let dropped = data.len() - transformed.len();
+    (transformed, dropped)
+}
+
+/// Replaces both coordinates with their natural logarithms, dropping points where either is
+/// non-positive; returns the transformed points and the number of points dropped.
+pub fn log_transform_xy(data: &[(f64, f64)]) -> (Vec<(f64, f64)>, usize) {
+    let transformed: Vec<(f64, f64)> = data
+        .iter()
+        .filter(|(x, y)| *x > 0.0 && *y > 0.0)
+        .map(|(x, y)| (x.ln(), y.ln()))
+        .collect();
+    let dropped = data.len() - transformed.len();
+    (transformed, dropped)
+}
+
+/// Elasticity of y with respect to x: the slope of ln(y) on ln(x), i.e. the percentage change
+/// in y per percentage change in x. Returns it with the number of non-positive points dropped.
+pub fn elasticity(data: &[(f64, f64)]) -> Result<(f64, usize), StatsError> {
+    let (transformed, dropped) = log_transform_xy(data);
+    let (slope, _) = calculate_regression(&transformed)?;
+    Ok((slope, dropped))
+}
+
+/// Calculates the coefficient of determination (R²) of a fitted regression line.
+/// Returns 0.0 when all y values are identical, since R² is undefined there, and fails
+/// on empty data.
+pub fn calculate_r_squared(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<f64, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let n = data.len() as f64;
+    let mean_y: f64 = data.iter().map(|(_, y)| *y).sum::<f64>() / n;
+
+    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    let ss_res: f64 = data
+        .iter()
+        .map(|(x, y)| (y - (slope * x + intercept)).powi(2))
+        .sum();
+
+    if ss_tot == 0.0 {
+        return Ok(0.0);
+    }
+
+    Ok(1.0 - ss_res / ss_tot)
+}
+
+/// Adjusts R² for the `k` predictors of a model fitted to `n` points, penalizing predictors
+/// that add little: `1 - (1 - R²)(n - 1) / (n - k - 1)`. Returns NaN with a warning when
+/// `n - k - 1 <= 0`, since no residual degrees of freedom are left.
+pub fn adjusted_r_squared(r2: f64, n: usize, k: usize) -> f64 {
+    if n <= k + 1 {
+        status!(
+            "Warning: adjusted R² is undefined for {} points and {} predictors",
+            n,
+            k
+        );
+        return f64::NAN;
+    }
+    1.0 - (1.0 - r2) * (n - 1) as f64 / (n - k - 1) as f64
+}
+
+/// Mean absolute difference between observed and fitted y values.
+/// Fails on empty data.
+pub fn mean_absolute_error(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<f64, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let total: f64 = data
+        .iter()
+        .map(|(x, y)| (y - predict(slope, intercept, *x)).abs())
+        .sum();
+    Ok(total / data.len() as f64)
+}
+
+/// Root mean squared difference between observed and fitted y values, in the units of y.
+/// Fails on empty data.
+pub fn root_mean_squared_error(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<f64, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let total: f64 = data
+        .iter()
+        .map(|(x, y)| (y - predict(slope, intercept, *x)).powi(2))
+        .sum();
+    Ok((total / data.len() as f64).sqrt())
+}
+
+/// Calculates the standard error of the slope of a fitted regression line.
+/// Fails with fewer than three points or no variance in x.
+pub fn slope_standard_error(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<f64, StatsError> {
+    let n = data.len();
+    if n < 3 {
+        return Err(StatsError::TooFewPoints {
+            needed: 3,
+            found: n,
+        });
+    }
+
+    let mean_x = data.iter().map(|(x, _)| *x).sum::<f64>() / n as f64;
+    let sxx: f64 = data.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+
+    let ss_res: f64 = data
+        .iter()
+        .map(|(x, y)| (y - (slope * x + intercept)).powi(2))
+        .sum();
+    Ok((ss_res / (n - 2) as f64 / sxx).sqrt())
+}
+
+/// Percentile bootstrap confidence interval of the slope at confidence `level` (e.g. 0.95).
+/// Each of the `iterations` resamples draws `data.len()` points with replacement from an
+/// RNG seeded with `seed` and refits the slope; resamples without variance in x are
+/// skipped. Fails with fewer than two points, an invalid level, or no usable resample.
+pub fn bootstrap_slope_ci(
+    data: &[(f64, f64)],
+    iterations: usize,
+    level: f64,
+    seed: u64,
+) -> Result<(f64, f64), StatsError> {
+    if !(level > 0.0 && level < 1.0) {
+        return Err(StatsError::InvalidLevel(level));
+    }
+    if data.len() < 2 {
+        return Err(StatsError::TooFewPoints {
+            needed: 2,
+            found: data.len(),
+        });
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut slopes = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let mut sums = RegressionSums::default();
+        for _ in 0..data.len() {
+            let (x, y) = data[rng.gen_range(0..data.len())];
+            sums.push(x, y);
+        }
+        if let Some((slope, _)) = sums.fit() {
+            slopes.push(slope);
+        }
+    }
+    if slopes.is_empty() {
+        return Err(StatsError::ZeroVariance);
+    }
+
+    slopes.sort_by(f64::total_cmp);
+    let tail = (1.0 - level) / 2.0;
+    Ok((
+        quantile_sorted(&slopes, tail),
+        quantile_sorted(&slopes, 1.0 - tail),
+    ))
+}
+
+/// Fraction of the full data's centered x sum of squares below which a leave-one-out
+/// refit is treated as having no variance in x, absorbing the rounding left by the downdate.
+const JACKKNIFE_VARIANCE_TOLERANCE: f64 = 1e-10;
+
+/// Estimates the standard error and bias of the slope by the leave-one-out jackknife,
+/// returned as `(standard_error, bias)`. Each of the n refits downdates the centered sums
+/// of the full data, `Sxx - n/(n-1) * (x_i - mean_x)^2` and likewise for `Sxy`, rather than
+/// summing the rest again, so the whole estimate takes linear time without the cancellation
+/// of subtracting raw sums. Fails with fewer than three points or if any refit has no
+/// variance in x.
+pub fn jackknife_slope(data: &[(f64, f64)]) -> Result<(f64, f64), StatsError> {
+    if data.len() < 3 {
+        return Err(StatsError::TooFewPoints {
+            needed: 3,
+            found: data.len(),
+        });
+    }
+    let n = data.len() as f64;
+    let mean_x = data.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = data.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (sxx, sxy) = data.iter().fold((0.0, 0.0), |(sxx, sxy), (x, y)| {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        (sxx + dx * dx, sxy + dx * dy)
+    });
+    if sxx == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+    let slope = sxy / sxx;
+
+    let scale = n / (n - 1.0);
+    let mut slopes = Vec::with_capacity(data.len());
+    for (x, y) in data {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        let rest_sxx = sxx - scale * dx * dx;
+        if rest_sxx <= sxx * JACKKNIFE_VARIANCE_TOLERANCE {
+            return Err(StatsError::ZeroVariance);
+        }
+        slopes.push((sxy - scale * dx * dy) / rest_sxx);
+    }
+
+    let mean_slope = slopes.iter().sum::<f64>() / n;
+    let variance = (n - 1.0) / n * slopes.iter().map(|s| (s - mean_slope).powi(2)).sum::<f64>();
+    Ok((variance.sqrt(), (n - 1.0) * (mean_slope - slope)))
+}
+
+/// Standard normal critical value for a two-sided 95% interval.
+// Intervals around a fitted line use `t_critical_95` with n - 2 degrees of freedom,
+// which approaches this value for large samples; the p-values of `f_test` and
+// `compare_slopes` still use the normal approximation.
+pub const CRITICAL_VALUE_95: f64 = 1.96;
+
+/// Calculates a 95% confidence interval for the slope, returned as (lower, upper), using
+/// Student's t with n - 2 degrees of freedom. Fails with fewer than three points or no
+/// variance in x.
+pub fn slope_confidence_interval(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<(f64, f64), StatsError> {
+    let se = slope_standard_error(data, slope, intercept)?;
+    let margin = t_critical_95(data.len() - 2) * se;
+    Ok((slope - margin, slope + margin))
+}
+
+/// Two-sided 95% critical values of Student's t for 1 to 30 degrees of freedom.
+const T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// Two-sided 95% critical value of Student's t with `df` degrees of freedom, looked up in a
+/// table up to 30 and from the Cornish-Fisher expansion around `CRITICAL_VALUE_95` above
+/// that (within 1e-4 of the exact quantile). Infinite for zero degrees of freedom.
+pub fn t_critical_95(df: usize) -> f64 {
+    match df {
+        0 => f64::INFINITY,
+        1..=30 => T_CRITICAL_95[df - 1],
+        _ => {
+            let (z, df) = (CRITICAL_VALUE_95, df as f64);
+            z + (z.powi(3) + z) / (4.0 * df)
+                + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+        }
+    }
+}
+
+/// Computes a 95% confidence band for the fitted line at `samples + 1` evenly spaced x
+/// values across the data, returned as `(x, lower, upper)`. The half-width at x is
+/// `t * s * sqrt(1/n + (x - mean_x)² / Sxx)`, where t is `t_critical_95(n - 2)` and s the
+/// residual standard deviation, so the band is narrowest at the mean of x. Fails with fewer
+/// than three points or no variance in x.
+pub fn confidence_band(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+    samples: usize,
+) -> Result<Vec<(f64, f64, f64)>, StatsError> {
+    let slope_se = slope_standard_error(data, slope, intercept)?;
+    let n = data.len() as f64;
+    let mean_x = data.iter().map(|(x, _)| *x).sum::<f64>() / n;
+    let sxx: f64 = data.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    // slope_se² = s² / Sxx
+    let residual_variance = slope_se.powi(2) * sxx;
+    let t = t_critical_95(data.len() - 2);
+    let (min_x, max_x) = data
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| {
+            (lo.min(*x), hi.max(*x))
+        });
+
+    Ok(
+        curve_points(min_x, max_x, samples, |x| predict(slope, intercept, x))
+            .into_iter()
+            .map(|(x, y)| {
+                let se = (residual_variance * (1.0 / n + (x - mean_x).powi(2) / sxx)).sqrt();
+                (x, y - t * se, y + t * se)
+            })
+            .collect(),
+    )
+}
+
+/// Complementary error function, using the rational approximation 7.1.26 of Abramowitz and
+/// Stegun (absolute error below 1.5e-7).
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let tail = poly * (-z * z).exp();
+    if x >= 0.0 {
+        tail
+    } else {
+        2.0 - tail
+    }
+}
+
+/// Tests whether a fitted line explains significantly more of the variance in y than the
+/// mean alone. Returns the F-statistic `(SS_reg / 1) / (SS_res / (n - 2))` and its
+/// approximate p-value, or an error with fewer than three points or no variance in y.
+// F(1, n - 2) is the square of Student's t with n - 2 degrees of freedom, which is
+// approximated by the standard normal as in `CRITICAL_VALUE_95`, giving
+// p = P(|Z| > sqrt(F)) = erfc(sqrt(F / 2)). This understates the p-value for small samples.
+pub fn f_test(data: &[(f64, f64)], slope: f64, intercept: f64) -> Result<(f64, f64), StatsError> {
+    let n = data.len();
+    if n < 3 {
+        return Err(StatsError::TooFewPoints {
+            needed: 3,
+            found: n,
+        });
+    }
+
+    let mean_y = data.iter().map(|(_, y)| *y).sum::<f64>() / n as f64;
+    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if ss_tot == 0.0 {
+        return Err(StatsError::ConstantValues);
+    }
+    let ss_res: f64 = data
+        .iter()
+        .map(|(x, y)| (y - (slope * x + intercept)).powi(2))
+        .sum();
+    if ss_res == 0.0 {
+        return Ok((f64::INFINITY, 0.0));
+    }
+
+    let f = (ss_tot - ss_res) / (ss_res / (n - 2) as f64);
+    Ok((f, erfc((f.max(0.0) / 2.0).sqrt())))
+}
+
+/// Difference between the slopes of two fitted relationships, with a z-test of whether it is
+/// zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlopeComparison {
+    /// Slope of the first relationship minus the slope of the second.
+    pub difference: f64,
+    pub standard_error: f64,
+    pub z: f64,
+    /// Approximate two-sided p-value of `z`.
+    pub p_value: f64,
+}
+
+/// Fits a line to each of `a` and `b` and tests whether the slope of `a` differs from the
+/// slope of `b`. Fails if either dataset has fewer than three points or no variance in x.
+// The standard error sqrt(se_a² + se_b²) treats the two fits as independent. Relationships
+// measured on the same artists share their y values, so their slopes are correlated and the
+// test is only a rough guide there. The p-value uses the normal approximation of
+// `CRITICAL_VALUE_95`.
+pub fn compare_slopes(a: &[(f64, f64)], b: &[(f64, f64)]) -> Result<SlopeComparison, StatsError> {
+    let slope_with_error = |data: &[(f64, f64)]| {
+        if data.len() < 3 {
+            return Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: data.len(),
+            });
+        }
+        let (slope, intercept) = calculate_regression(data)?;
+        let se = slope_standard_error(data, slope, intercept)?;
+        Ok((slope, se))
+    };
+    let (slope_a, se_a) = slope_with_error(a)?;
+    let (slope_b, se_b) = slope_with_error(b)?;
+
+    let difference = slope_a - slope_b;
+    let standard_error = (se_a.powi(2) + se_b.powi(2)).sqrt();
+    let z = difference / standard_error;
+    Ok(SlopeComparison {
+        difference,
+        standard_error,
+        z,
+        p_value: erfc(z.abs() / std::f64::consts::SQRT_2),
+    })
+}
+
+/// Calculates the Pearson correlation coefficient between x and y.
+/// Returns 0.0 when either variable has zero variance, and fails on empty data.
+pub fn pearson_correlation(data: &[(f64, f64)]) -> Result<f64, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let n = data.len() as f64;
+    let mean_x: f64 = data.iter().map(|(x, _)| *x).sum::<f64>() / n;
+    let mean_y: f64 = data.iter().map(|(_, y)| *y).sum::<f64>() / n;
+
+    let cov: f64 = data.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let var_x: f64 = data.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let var_y: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return Ok(0.0);
+    }
+
+    Ok((cov / (var_x.sqrt() * var_y.sqrt())).clamp(-1.0, 1.0))
+}
+
+/// Ranks `values` from 1 to n, giving tied values the average of the ranks they span.
+pub fn average_ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // Positions start..end hold tied values sharing ranks start + 1 through end.
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// Calculates the Spearman rank correlation coefficient between x and y.
+/// Fails on empty data.
+pub fn spearman_correlation(data: &[(f64, f64)]) -> Result<f64, StatsError> {
+    let xs: Vec<f64> = data.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<f64> = data.iter().map(|(_, y)| *y).collect();
+    let ranked: Vec<(f64, f64)> = average_ranks(&xs)
+        .into_iter()
+        .zip(average_ranks(&ys))
+        .collect();
+    pearson_correlation(&ranked)
+}
+
+/// Descriptive statistics of a single variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryStats {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Computes summary statistics of `values`. The standard deviation is the sample
+/// standard deviation (n - 1 denominator). All fields except `count` are NaN for empty input.
+pub fn compute_summary(values: &[f64]) -> SummaryStats {
+    let count = values.len();
+    if count == 0 {
+        return SummaryStats {
+            count,
+            mean: f64::NAN,
+            median: f64::NAN,
+            std_dev: f64::NAN,
+            min: f64::NAN,
+            max: f64::NAN,
+        };
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let mean = values.iter().sum::<f64>() / count as f64;
+    let median = quantile_sorted(&sorted, 0.5);
+    let std_dev = if count > 1 {
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt()
+    } else {
+        0.0
+    };
+
+    SummaryStats {
+        count,
+        mean,
+        median,
+        std_dev,
+        min: sorted[0],
+        max: sorted[count - 1],
+    }
+}
+
+/// Sorts a copy of `values` and counts how many values `proportion` trims from each end,
+/// validating both.
+fn trim_count(values: &[f64], proportion: f64) -> Result<(Vec<f64>, usize), StatsError> {
+    if !(0.0..0.5).contains(&proportion) {
+        return Err(StatsError::InvalidProportion(proportion));
+    }
+    if values.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let k = (values.len() as f64 * proportion).floor() as usize;
+    Ok((sorted, k))
+}
+
+/// Mean of `values` after dropping the lowest and highest `proportion` of them (rounded
+/// down to whole values). Fails on empty input or a proportion outside [0, 0.5).
+pub fn trimmed_mean(values: &[f64], proportion: f64) -> Result<f64, StatsError> {
+    let (sorted, k) = trim_count(values, proportion)?;
+    let kept = &sorted[k..sorted.len() - k];
+    Ok(kept.iter().sum::<f64>() / kept.len() as f64)
+}
+
+/// Clamps the lowest and highest `proportion` of `values` to the nearest value kept by
+/// `trimmed_mean`, preserving their order. Fails on empty input or a proportion outside
+/// [0, 0.5).
+pub fn winsorize(values: &[f64], proportion: f64) -> Result<Vec<f64>, StatsError> {
+    let (sorted, k) = trim_count(values, proportion)?;
+    let (low, high) = (sorted[k], sorted[sorted.len() - 1 - k]);
+    Ok(values.iter().map(|v| v.clamp(low, high)).collect())
+}
+
+/// Second, third and fourth central moments of `values` (n denominator).
+fn central_moments(values: &[f64]) -> Result<(f64, f64, f64), StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let (m2, m3, m4) = values.iter().fold((0.0, 0.0, 0.0), |(m2, m3, m4), v| {
+        let d = v - mean;
+        (m2 + d * d, m3 + d * d * d, m4 + d * d * d * d)
+    });
+    if m2 == 0.0 {
+        return Err(StatsError::ConstantValues);
+    }
+    Ok((m2 / n, m3 / n, m4 / n))
+}
+
+/// Skewness of `values` (the third standardized moment): positive when the right tail
+/// is longer, zero for a symmetric distribution. Fails on empty or constant input.
+pub fn skewness(values: &[f64]) -> Result<f64, StatsError> {
+    let (m2, m3, _) = central_moments(values)?;
+    Ok(m3 / m2.powf(1.5))
+}
+
+/// Excess kurtosis of `values` (the fourth standardized moment minus 3): zero for a
+/// normal distribution, positive for heavier tails. Fails on empty or constant input.
+pub fn excess_kurtosis(values: &[f64]) -> Result<f64, StatsError> {
+    let (m2, _, m4) = central_moments(values)?;
+    Ok(m4 / (m2 * m2) - 3.0)
+}
+
+/// Averages each value with its neighbours in a centered window of `window` values.
+/// Near the ends the window shrinks to the values available, so the output has the same
+/// length as `values`. Fails if `window` is zero.
+pub fn moving_average(values: &[f64], window: usize) -> Result<Vec<f64>, StatsError> {
+    if window == 0 {
+        return Err(StatsError::ZeroWindow);
+    }
+    let before = (window - 1) / 2;
+    let after = window / 2;
+    Ok((0..values.len())
+        .map(|i| {
+            let span = &values[i.saturating_sub(before)..(i + after + 1).min(values.len())];
+            span.iter().sum::<f64>() / span.len() as f64
+        })
+        .collect())
+}
+
+/// Converts values to z-scores using the sample mean and standard deviation.
+/// Values with zero spread all map to 0.0.
+pub fn standardize(values: &[f64]) -> Vec<f64> {
+    let SummaryStats { mean, std_dev, .. } = compute_summary(values);
+    values
+        .iter()
+        .map(|v| {
+            if std_dev > 0.0 {
+                (v - mean) / std_dev
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Standardized coefficient of a simple regression slope: the change in standard deviations
+/// of y per standard deviation of x, `slope * sd_x / sd_y`. Unlike the raw slope it is
+/// comparable across predictors on different scales (for a least-squares slope it equals
+/// the Pearson correlation). Fails with fewer than two points or when either variable is
+/// constant.
+pub fn standardized_slope(data: &[(f64, f64)], slope: f64) -> Result<f64, StatsError> {
+    if data.len() < 2 {
+        return Err(StatsError::TooFewPoints {
+            needed: 2,
+            found: data.len(),
+        });
+    }
+    let xs: Vec<f64> = data.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<f64> = data.iter().map(|(_, y)| *y).collect();
+    let (sd_x, sd_y) = (compute_summary(&xs).std_dev, compute_summary(&ys).std_dev);
+    if sd_x == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+    if sd_y == 0.0 {
+        return Err(StatsError::ConstantValues);
+    }
+    Ok(slope * sd_x / sd_y)
+}
+
+/// Share of the summed total streams contributed by each stream component, in percent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamBreakdown {
+    pub solo_percent: f64,
+    pub feature_percent: f64,
+    pub lead_percent: f64,
+}
+
+/// Sums each stream component across all artists and expresses it as a
+/// percentage of the summed total streams.
+pub fn stream_breakdown(data: &[ArtistData]) -> StreamBreakdown {
+    let total: f64 = data.iter().map(|d| d.total_streams).sum();
+    let percent =
+        |component: fn(&ArtistData) -> f64| 100.0 * data.iter().map(component).sum::<f64>() / total;
+
+    StreamBreakdown {
+        solo_percent: percent(|d| d.solo_streams),
+        feature_percent: percent(|d| d.feature_streams),
+        lead_percent: percent(|d| d.lead_streams),
+    }
+}
+
+/// Returns the artists whose solo, feature, and lead streams do not add up to their
+/// total streams within `tolerance`, given as a fraction of the total.
+pub fn inconsistent_artists(data: &[ArtistData], tolerance: f64) -> Vec<&ArtistData> {
+    data.iter()
+        .filter(|d| {
+            let components = d.solo_streams + d.feature_streams + d.lead_streams;
+            (components - d.total_streams).abs() > tolerance * d.total_streams.abs()
+        })
+        .collect()
+}
+
+/// Prints summary statistics as a table with one row per named variable.
+pub fn print_summary_table(rows: &[(&str, SummaryStats)]) {
+    println!(
+        "{:<16} {:>8} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "Variable", "Count", "Mean", "Median", "Std Dev", "Min", "Max"
+    );
+    for (name, stats) in rows {
+        println!(
+            "{:<16} {:>8} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2}",
+            name, stats.count, stats.mean, stats.median, stats.std_dev, stats.min, stats.max
+        );
+    }
+}
+
+/// Names of the four stream variables, in the order used by `stream_variables`.
+pub const VARIABLE_NAMES: [&str; 4] = [
+    "Total Streams",
+    "Solo Streams",
+    "Featured Streams",
+    "Lead Streams",
+];
+
+/// The total, solo, featured and lead streams of an artist.
+pub fn stream_variables(d: &ArtistData) -> [f64; 4] {
+    [
+        d.total_streams,
+        d.solo_streams,
+        d.feature_streams,
+        d.lead_streams,
+    ]
+}
+
+/// Calculates the sample covariance matrix of the four stream variables, ordered as in
+/// `VARIABLE_NAMES`. Returns a zero matrix when there are fewer than two records.
+pub fn covariance_matrix(data: &[ArtistData]) -> [[f64; 4]; 4] {
+    let mut matrix = [[0.0; 4]; 4];
+    if data.len() < 2 {
+        return matrix;
+    }
+
+    let n = data.len() as f64;
+    let mut means = [0.0; 4];
+    for d in data {
+        for (mean, v) in means.iter_mut().zip(stream_variables(d)) {
+            *mean += v / n;
+        }
+    }
+
+    for d in data {
+        let values = stream_variables(d);
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell += (values[i] - means[i]) * (values[j] - means[j]) / (n - 1.0);
+            }
+        }
+    }
+    matrix
+}
+
+/// Converts a covariance matrix into Pearson correlations by dividing each entry by the
+/// product of the two standard deviations. Entries involving a constant variable are 0.0.
+pub fn correlation_matrix(data: &[ArtistData]) -> [[f64; 4]; 4] {
+    let covariance = covariance_matrix(data);
+    let mut matrix = [[0.0; 4]; 4];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            let scale = (covariance[i][i] * covariance[j][j]).sqrt();
+            if scale > 0.0 {
+                *cell = (covariance[i][j] / scale).clamp(-1.0, 1.0);
+            }
+        }
+    }
+    matrix
+}
+
+/// Negative and positive ends of the correlation heatmap's diverging colormap.
+const CORRELATION_NEGATIVE: RGBColor = RGBColor(33, 102, 172);
+const CORRELATION_POSITIVE: RGBColor = RGBColor(178, 24, 43);
+
+/// Color of a correlation in [-1, 1], fading from blue through white at 0 to red.
+fn correlation_color(value: f64) -> RGBColor {
+    let t = value.clamp(-1.0, 1.0);
+    let end = if t < 0.0 {
+        CORRELATION_NEGATIVE
+    } else {
+        CORRELATION_POSITIVE
+    };
+    let channel = |end: u8| (255.0 + (end as f64 - 255.0) * t.abs()).round() as u8;
+    RGBColor(channel(end.0), channel(end.1), channel(end.2))
+}
+
+/// Visualizes a correlation matrix as a grid of cells shaded by a diverging colormap
+/// centered at 0, with the value printed in each cell. Row `i` and column `i` are
+/// labeled `labels[i]`, with the first row at the top.
+pub fn visualize_correlation_heatmap(
+    matrix: &[[f64; 4]; 4],
+    labels: &[&str; 4],
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let size = labels.len() as f64;
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            "Correlation Matrix",
+            ("sans-serif", style.caption_font_size),
+        )
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(120)
+        .build_cartesian_2d(0.0..size, 0.0..size)?;
+
+    let label_at = |value: &f64| {
+        let i = value.floor() as usize;
+        if value.fract() == 0.5 && i < labels.len() {
+            labels[i].to_string()
+        } else {
+            String::new()
+        }
+    };
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_labels(labels.len() * 2 + 1)
+        .y_labels(labels.len() * 2 + 1)
+        .x_label_formatter(&label_at)
+        .y_label_formatter(&|y| label_at(&(size - y)))
+        .draw()?;
+
+    let cells = matrix
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, value)| (i, j, *value)));
+    chart.draw_series(cells.clone().map(|(i, j, value)| {
+        let top = size - i as f64;
+        Rectangle::new(
+            [(j as f64, top), (j as f64 + 1.0, top - 1.0)],
+            correlation_color(value).filled(),
+        )
+    }))?;
+
+    let centered = Pos::new(HPos::Center, VPos::Center);
+    chart.draw_series(cells.map(|(i, j, value)| {
+        let color = if value.abs() > 0.6 { &WHITE } else { &BLACK };
+        let font = TextStyle::from(("sans-serif", 24).into_font())
+            .color(color)
+            .pos(centered);
+        Text::new(
+            format!("{:.2}", value),
+            (j as f64 + 0.5, size - i as f64 - 0.5),
+            font,
+        )
+    }))?;
+
+    root.present()?;
+    info!("Correlation heatmap saved to {}", file_name);
+    Ok(())
+}
+
+/// Prints a matrix over the stream variables as a table labeled by `VARIABLE_NAMES`.
+pub fn print_matrix(title: &str, matrix: &[[f64; 4]; 4], precision: usize) {
+    println!("{}:", title);
+    print!("{:<16}", "");
+    for name in VARIABLE_NAMES {
+        print!(" {:>16}", name);
+    }
+    println!();
+    for (name, row) in VARIABLE_NAMES.iter().zip(matrix) {
+        print!("{:<16}", name);
+        for value in row {
+            print!(" {:>16.*}", precision, value);
+        }
+        println!();
+    }
+}
+
+/// Solves the linear system `a * x = b` by Gaussian elimination with partial pivoting.
+/// Returns `None` when the matrix is singular.
+pub fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col] == 0.0 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot_value) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Returns the solo, feature, and lead streams of an artist, in that order.
+pub fn predictors(d: &ArtistData) -> [f64; 3] {
+    [d.solo_streams, d.feature_streams, d.lead_streams]
+}
+
+/// Absolute correlation above which two predictors are considered collinear.
+pub const COLLINEARITY_THRESHOLD: f64 = 0.99;
+
+/// Checks the solo, feature, and lead streams pairwise for collinearity, failing with the
+/// most correlated pair whose absolute correlation exceeds `threshold`.
+pub fn check_collinearity(data: &[ArtistData], threshold: f64) -> Result<(), StatsError> {
+    let correlations = correlation_matrix(data);
+    let worst = [(1, 2), (1, 3), (2, 3)]
+        .into_iter()
+        .map(|(i, j)| (i, j, correlations[i][j]))
+        .filter(|(_, _, r)| r.abs() > threshold)
+        .max_by(|a, b| a.2.abs().total_cmp(&b.2.abs()));
+    match worst {
+        Some((i, j, correlation)) => Err(StatsError::Collinear {
+            first: VARIABLE_NAMES[i],
+            second: VARIABLE_NAMES[j],
+            correlation,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fits total streams against solo, feature, and lead streams jointly using the normal equations.
+/// Returns `[intercept, solo, feature, lead]` coefficients. Fails before solving if two
+/// predictors are collinear (see `check_collinearity`), or if the system is singular.
+pub fn multiple_regression(data: &[ArtistData]) -> Result<Vec<f64>, StatsError> {
+    check_collinearity(data, COLLINEARITY_THRESHOLD)?;
+    let mut xtx = vec![vec![0.0; 4]; 4];
+    let mut xty = vec![0.0; 4];
+
+    for d in data {
+        let [solo, feature, lead] = predictors(d);
+        let row = [1.0, solo, feature, lead];
+        for i in 0..4 {
+            for j in 0..4 {
+                xtx[i][j] += row[i] * row[j];
+            }
+            xty[i] += row[i] * d.total_streams;
+        }
+    }
+
+    solve_linear_system(xtx, xty).ok_or(StatsError::SingularSystem)
+}
+
+/// Fits total streams against solo, feature, and lead streams with an L2 penalty of `lambda`,
+/// shrinking the coefficients to keep them stable when the predictors are correlated.
+/// Predictors are standardized and the response centered before `lambda * I` is added to the
+/// normal equations, so the penalty weighs every predictor alike and leaves the intercept
+/// alone; since each standardized predictor's sum of squares is `n - 1`, `lambda` is relative
+/// to the number of records. Coefficients are returned on the original scale as
+/// `[intercept, solo, feature, lead]`, like `multiple_regression`, which `lambda = 0` recovers.
+pub fn ridge_regression(data: &[ArtistData], lambda: f64) -> Result<Vec<f64>, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    let columns: Vec<Vec<f64>> = (0..3)
+        .map(|j| data.iter().map(|d| predictors(d)[j]).collect())
+        .collect();
+    let scales: Vec<(f64, f64)> = columns
+        .iter()
+        .map(|column| {
+            let SummaryStats { mean, std_dev, .. } = compute_summary(column);
+            (mean, std_dev)
+        })
+        .collect();
+    if scales.iter().any(|(_, std_dev)| *std_dev == 0.0) {
+        return Err(StatsError::ZeroVariance);
+    }
+    let mean_y = data.iter().map(|d| d.total_streams).sum::<f64>() / data.len() as f64;
+
+    let mut xtx = vec![vec![0.0; 3]; 3];
+    let mut xty = vec![0.0; 3];
+    for (k, d) in data.iter().enumerate() {
+        let z: Vec<f64> = (0..3)
+            .map(|j| (columns[j][k] - scales[j].0) / scales[j].1)
+            .collect();
+        for i in 0..3 {
+            for j in 0..3 {
+                xtx[i][j] += z[i] * z[j];
+            }
+            xty[i] += z[i] * (d.total_streams - mean_y);
+        }
+    }
+    for (i, row) in xtx.iter_mut().enumerate() {
+        row[i] += lambda;
+    }
+
+    let standardized = solve_linear_system(xtx, xty).ok_or(StatsError::SingularSystem)?;
+    let slopes: Vec<f64> = standardized
+        .iter()
+        .zip(&scales)
+        .map(|(beta, (_, std_dev))| beta / std_dev)
+        .collect();
+    let intercept = mean_y
+        - slopes
+            .iter()
+            .zip(&scales)
+            .map(|(b, (mean, _))| b * mean)
+            .sum::<f64>();
+    Ok(std::iter::once(intercept).chain(slopes).collect())
+}
+
+/// Calculates R² of a multiple regression fit returned by `multiple_regression`.
+pub fn multiple_r_squared(data: &[ArtistData], coefficients: &[f64]) -> f64 {
+    let n = data.len() as f64;
+    let mean_y: f64 = data.iter().map(|d| d.total_streams).sum::<f64>() / n;
+
+    let ss_tot: f64 = data
+        .iter()
+        .map(|d| (d.total_streams - mean_y).powi(2))
+        .sum();
+    let ss_res: f64 = data
+        .iter()
+        .map(|d| {
+            let predicted = coefficients[0]
+                + predictors(d)
+                    .iter()
+                    .zip(&coefficients[1..])
+                    .map(|(x, b)| x * b)
+                    .sum::<f64>();
+            (d.total_streams - predicted).powi(2)
+        })
+        .sum();
+
+    if ss_tot == 0.0 {
+        return 0.0;
+    }
+
+    1.0 - ss_res / ss_tot
+}
+
+/// Fits a polynomial of the given degree by least squares.
+/// Returns the coefficients in ascending powers of x. Fails when there are no more points
+/// than the degree, every x is zero, or the normal equations are singular.
+pub fn calculate_polynomial_regression(
+    data: &[(f64, f64)],
+    degree: usize,
+) -> Result<Vec<f64>, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    if data.len() <= degree {
+        return Err(StatsError::TooFewPoints {
+            needed: degree + 1,
+            found: data.len(),
+        });
+    }
+    // Fit on x scaled into [-1, 1] to keep the normal equations well conditioned.
+    let scale = data.iter().map(|(x, _)| x.abs()).fold(0.0, f64::max);
+    if scale == 0.0 {
+        return Err(StatsError::ZeroVariance);
+    }
+
+    let terms = degree + 1;
+    let mut xtx = vec![vec![0.0; terms]; terms];
+    let mut xty = vec![0.0; terms];
+
+    for (x, y) in data {
+        let powers: Vec<f64> = (0..terms).map(|k| (x / scale).powi(k as i32)).collect();
+        for i in 0..terms {
+            for j in 0..terms {
+                xtx[i][j] += powers[i] * powers[j];
+            }
+            xty[i] += powers[i] * y;
+        }
+    }
+
+    let scaled = solve_linear_system(xtx, xty).ok_or(StatsError::SingularSystem)?;
+    Ok(scaled
+        .iter()
+        .enumerate()
+        .map(|(k, c)| c / scale.powi(k as i32))
+        .collect())
+}
+
+/// Evaluates a polynomial with coefficients in ascending powers of x.
+pub fn evaluate_polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Formats polynomial coefficients as an equation such as `y = 1.00 - 2.0000e0x + 3.0000e-4x^2`,
+/// subtracting the magnitude of each negative term after the constant.
+pub fn format_polynomial(coefficients: &[f64]) -> String {
+    let mut equation = String::from("y =");
+    for (k, c) in coefficients.iter().enumerate() {
+        let term = match k {
+            0 => format!(" {:.2}", c),
+            1 => format!("{:.4e}x", c.abs()),
+            _ => format!("{:.4e}x^{}", c.abs(), k),
+        };
+        if k > 0 {
+            equation.push_str(if c.is_sign_negative() { " - " } else { " + " });
+        }
+        equation.push_str(&term);
+    }
+    equation
+}
+
+/// Goodness of fit of one candidate model, measured on the original y scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFit {
+    pub name: &'static str,
+    /// Number of fitted coefficients.
+    pub parameters: usize,
+    pub r_squared: f64,
+    /// Akaike information criterion, `n ln(SSE / n) + 2k`; lower is better.
+    pub aic: f64,
+}
+
+/// Scores predictions against the observed y values by R² and AIC.
+fn score_model(
+    data: &[(f64, f64)],
+    name: &'static str,
+    parameters: usize,
+    f: impl Fn(f64) -> f64,
+) -> ModelFit {
+    let n = data.len() as f64;
+    let mean_y = data.iter().map(|(_, y)| *y).sum::<f64>() / n;
+    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    let ss_res: f64 = data.iter().map(|(x, y)| (y - f(*x)).powi(2)).sum();
+    ModelFit {
+        name,
+        parameters,
+        r_squared: if ss_tot == 0.0 {
+            0.0
+        } else {
+            1.0 - ss_res / ss_tot
+        },
+        aic: n * (ss_res / n).ln() + 2.0 * parameters as f64,
+    }
+}
+
+/// Fits linear, log-y (exponential) and quadratic models to the same data and returns them
+/// ranked by AIC, best first. The log-y model is fitted on the points with positive y but
+/// scored on all of them, so every criterion is on the same scale. Candidates that cannot be
+/// fitted are left out; fails only if the linear fit does.
+pub fn compare_models(data: &[(f64, f64)]) -> Result<Vec<ModelFit>, StatsError> {
+    let (slope, intercept) = calculate_regression(data)?;
+    let mut fits = vec![score_model(data, "linear", 2, |x| {
+        predict(slope, intercept, x)
+    })];
+    if let Ok((log_slope, log_intercept)) = calculate_regression(&log_transform_y(data).0) {
+        fits.push(score_model(data, "exponential (log-y)", 2, |x| {
+            predict(log_slope, log_intercept, x).exp()
+        }));
+    }
+    if let Ok(coefficients) = calculate_polynomial_regression(data, 2) {
+        fits.push(score_model(data, "quadratic", 3, |x| {
+            evaluate_polynomial(&coefficients, x)
+        }));
+    }
+    fits.sort_by(|a, b| a.aic.total_cmp(&b.aic));
+    Ok(fits)
+}
+
+/// Returns the indices of the `n` points lying furthest from the regression line.
+pub fn top_outlier_indices(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+    n: usize,
+) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..data.len()).collect();
+    let residual = |i: usize| {
+        let (x, y) = data[i];
+        (y - (slope * x + intercept)).abs()
+    };
+    indices.sort_by(|&a, &b| residual(b).total_cmp(&residual(a)));
+    indices.truncate(n);
+    indices
+}
+
+/// Calculates the upper x and y axis bounds of a chart, ignoring NaN values.
+/// Returns an error when there is no finite data to plot.
+pub fn chart_bounds(data: &[(f64, f64)]) -> Result<(f64, f64), Box<dyn Error>> {
+    let max_x = data.iter().map(|(x, _)| *x).reduce(f64::max);
+    let max_y = data.iter().map(|(_, y)| *y).reduce(f64::max);
+
+    match (max_x, max_y) {
+        (Some(max_x), Some(max_y)) if max_x.is_finite() && max_y.is_finite() => Ok((max_x, max_y)),
+        (None, _) | (_, None) => Err("cannot plot an empty dataset".into()),
+        _ => Err("cannot plot a dataset without finite values".into()),
+    }
+}
+
+/// Fraction of an axis's span left empty beyond the outermost plotted value.
+pub const AXIS_PADDING: f64 = 0.05;
+
+/// Linear axis range covering `min..=max` plus `AXIS_PADDING` of the span on each open side.
+/// The axis starts at 0 unless `min` is negative, in which case it is padded below as well.
+pub fn padded_range(min: f64, max: f64) -> (f64, f64) {
+    let lower = min.min(0.0);
+    let span = max - lower;
+    let pad = if span > 0.0 { span * AXIS_PADDING } else { 1.0 };
+    let start = if lower < 0.0 { lower - pad } else { 0.0 };
+    (start, max + pad)
+}
+
+/// Color used for points flagged as outliers.
+const ORANGE: RGBColor = RGBColor(255, 165, 0);
+
+/// Color of the line fitted through the origin.
+const GRAY: RGBColor = RGBColor(128, 128, 128);
+
+/// Color of the moving average line, darker than the `GREEN` of the log-y curve.
+const DARK_GREEN: RGBColor = RGBColor(0, 128, 0);
+
+/// Returns the indices of values whose absolute z-score exceeds `threshold`.
+pub fn find_outliers(values: &[f64], threshold: f64) -> Vec<usize> {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+
+    if std_dev == 0.0 || !std_dev.is_finite() {
+        return Vec::new();
+    }
+
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| ((*v - mean) / std_dev).abs() > threshold)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Marks the values outside `[Q1 - k * IQR, Q3 + k * IQR]` as outliers, with quartiles from
+/// `quantile_sorted`. The result is parallel to `values`.
+pub fn filter_iqr_outliers(values: &[f64], k: f64) -> Vec<bool> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = quantile_sorted(&sorted, 0.25);
+    let q3 = quantile_sorted(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let (low, high) = (q1 - k * iqr, q3 + k * iqr);
+    values.iter().map(|v| *v < low || *v > high).collect()
+}
+
+/// Output dimensions and text sizing shared by every chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartStyle {
+    pub width: u32,
+    pub height: u32,
+    pub caption_font_size: u32,
+    pub margin: u32,
+    pub mesh: MeshConfig,
+    /// Radius in pixels of the points of scatter and residual plots.
+    pub point_radius: u32,
+    /// Opacity of those points, from 0.0 (invisible) to 1.0 (solid).
+    pub point_alpha: f64,
+    /// Colors of each relationship's scatter plot.
+    pub palette: Palette,
+    /// Number of segments fitted curves are drawn with across the x range.
+    pub line_samples: usize,
+}
+
+impl Default for ChartStyle {
+    fn default() -> Self {
+        ChartStyle {
+            width: 1024,
+            height: 768,
+            caption_font_size: 40,
+            margin: 20,
+            mesh: MeshConfig::default(),
+            point_radius: 5,
+            point_alpha: 1.0,
+            palette: Palette::default(),
+            line_samples: CURVE_SAMPLES,
+        }
+    }
+}
+
+/// Point and line colors of a scatter plot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeriesColors {
+    pub point: RGBColor,
+    pub line: RGBColor,
+}
+
+impl Default for SeriesColors {
+    fn default() -> Self {
+        SeriesColors {
+            point: RED,
+            line: BLUE,
+        }
+    }
+}
+
+/// Scatter plot colors of each relationship, keyed by `Relationship::key`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub entries: Vec<(String, SeriesColors)>,
+}
+
+impl Palette {
+    /// Colors of the relationship `key`, or the default red points and blue line if the
+    /// palette has no entry for it.
+    pub fn colors(&self, key: &str) -> SeriesColors {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, colors)| *colors)
+            .unwrap_or_default()
+    }
+
+    /// Sets the colors of the relationship `key`, replacing any previous entry.
+    pub fn set(&mut self, key: &str, colors: SeriesColors) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = colors,
+            None => self.entries.push((key.to_string(), colors)),
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let entry = |key: &str, point, line| (key.to_string(), SeriesColors { point, line });
+        Palette {
+            entries: vec![
+                entry("solo", RED, BLUE),
+                entry("feature", GREEN, BLACK),
+                entry("lead", PURPLE, TEAL),
+            ],
+        }
+    }
+}
+
+/// Points of the `lead` relationship in the default palette.
+const PURPLE: RGBColor = RGBColor(128, 0, 128);
+
+/// Regression line of the `lead` relationship in the default palette.
+const TEAL: RGBColor = RGBColor(0, 128, 128);
+
+/// Parses a color given as `#rrggbb` hex or one of the names red, green, blue, black,
+/// purple, teal, orange, cyan, magenta, or yellow.
+pub fn parse_color(text: &str) -> Result<RGBColor, String> {
+    let hex = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match text.to_lowercase().as_str() {
+        "red" => Ok(RED),
+        "green" => Ok(GREEN),
+        "blue" => Ok(BLUE),
+        "black" => Ok(BLACK),
+        "purple" => Ok(PURPLE),
+        "teal" => Ok(TEAL),
+        "orange" => Ok(ORANGE),
+        "cyan" => Ok(CYAN),
+        "magenta" => Ok(MAGENTA),
+        "yellow" => Ok(YELLOW),
+        code if code.len() == 7 && code.is_ascii() && code.starts_with('#') => {
+            match (hex(&code[1..3]), hex(&code[3..5]), hex(&code[5..7])) {
+                (Some(r), Some(g), Some(b)) => Ok(RGBColor(r, g, b)),
+                _ => Err(format!("invalid hex color '{}'", text)),
+            }
+        }
+        _ => Err(format!(
+            "unknown color '{}', expected a name or #rrggbb",
+            text
+        )),
+    }
+}
+
+/// Gridline and tick settings of the scatter and residual plots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshConfig {
+    /// Approximate number of labeled ticks on each axis.
+    pub x_labels: usize,
+    pub y_labels: usize,
+    /// Draw lighter minor gridlines between the labeled ones.
+    pub minor_grid: bool,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        MeshConfig {
+            x_labels: 11,
+            y_labels: 11,
+            minor_grid: true,
+        }
+    }
+}
+
+impl MeshConfig {
+    /// Applies the tick counts and minor gridline setting to a chart mesh.
+    pub fn apply<X: Ranged, Y: Ranged, DB: DrawingBackend>(
+        &self,
+        mesh: &mut MeshStyle<'_, '_, X, Y, DB>,
+    ) {
+        mesh.x_labels(self.x_labels).y_labels(self.y_labels);
+        if !self.minor_grid {
+            mesh.max_light_lines(0);
+        }
+    }
+}
+
+/// Optional extras drawn on a scatter plot.
+#[derive(Debug, Clone, Default)]
+pub struct PlotOptions {
+    /// Text drawn next to the given points.
+    pub labels: Vec<((f64, f64), String)>,
+    /// Indices of points drawn in orange.
+    pub outliers: Vec<usize>,
+    /// Draw both axes logarithmically, omitting non-positive points.
+    pub log_scale: bool,
+    /// Polynomial coefficients, in ascending powers of x, of a curve drawn alongside the line.
+    pub polynomial: Option<Vec<f64>>,
+    /// `(slope, intercept)` of a fit of ln(y) on x, drawn as the curve `y = e^(intercept + slope * x)`.
+    pub exponential: Option<(f64, f64)>,
+    /// `(slope, intercept)` of a robust line drawn alongside the least-squares one.
+    pub robust: Option<(f64, f64)>,
+    /// A predicted point marked with a star and labeled with its y value.
+    pub highlight: Option<(f64, f64)>,
+    /// A smoothed series, in ascending x order, drawn as a moving average line.
+    pub smoothed: Option<Vec<(f64, f64)>>,
+    /// Colors of the non-outlier points and the regression line.
+    pub colors: SeriesColors,
+    /// Slope of a line through the origin drawn alongside the least-squares one.
+    pub origin_slope: Option<f64>,
+    /// Fit statistics listed in a box in one corner of the plot.
+    pub stats_box: Option<StatsBox>,
+    /// Corner of the plotting area holding `stats_box`.
+    pub stats_corner: Corner,
+    /// Caption drawn instead of the relationship name.
+    pub title: Option<String>,
+    /// `(x, lower, upper)` bounds of a confidence band shaded around the regression line.
+    pub confidence_band: Option<Vec<(f64, f64, f64)>>,
+}
+
+/// Fit statistics shown in the annotation box of a scatter plot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsBox {
+    pub n: usize,
+    pub r_squared: f64,
+    pub correlation: f64,
+}
+
+impl StatsBox {
+    /// The lines of text drawn in the box.
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("n = {}", self.n),
+            format!("R² = {:.4}", self.r_squared),
+            format!("r = {:.4}", self.correlation),
+        ]
+    }
+}
+
+/// A corner of a chart's plotting area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl std::str::FromStr for Corner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            _ => Err(format!(
+                "unknown corner '{}', expected top-left, top-right, bottom-left or bottom-right",
+                s
+            )),
+        }
+    }
+}
+
+/// Font size of the text in a stats box.
+const STATS_BOX_FONT_SIZE: u32 = 16;
+
+/// Space in pixels between a stats box's border and its text, and between the box and the
+/// edges of the plotting area.
+const STATS_BOX_PADDING: i32 = 8;
+
+/// Draws `lines` in a bordered, translucent white box in `corner` of the plotting area at
+/// pixel ranges `plot_area`.
+fn draw_stats_box<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    plot_area: (std::ops::Range<i32>, std::ops::Range<i32>),
+    lines: &[String],
+    corner: Corner,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let font: TextStyle = ("sans-serif", STATS_BOX_FONT_SIZE).into();
+    let line_height = STATS_BOX_FONT_SIZE as i32 + 4;
+    let mut text_width = 0;
+    for line in lines {
+        text_width = text_width.max(root.estimate_text_size(line, &font)?.0 as i32);
+    }
+    let width = text_width + 2 * STATS_BOX_PADDING;
+    let height = lines.len() as i32 * line_height + 2 * STATS_BOX_PADDING;
+
+    let (x_range, y_range) = plot_area;
+    let left = match corner {
+        Corner::TopLeft | Corner::BottomLeft => x_range.start + STATS_BOX_PADDING,
+        Corner::TopRight | Corner::BottomRight => x_range.end - STATS_BOX_PADDING - width,
+    };
+    let top = match corner {
+        Corner::TopLeft | Corner::TopRight => y_range.start + STATS_BOX_PADDING,
+        Corner::BottomLeft | Corner::BottomRight => y_range.end - STATS_BOX_PADDING - height,
+    };
+
+    let corners = [(left, top), (left + width, top + height)];
+    root.draw(&Rectangle::new(corners, WHITE.mix(0.85).filled()))?;
+    root.draw(&Rectangle::new(corners, BLACK))?;
+    for (i, line) in lines.iter().enumerate() {
+        root.draw(&Text::new(
+            line.clone(),
+            (
+                left + STATS_BOX_PADDING,
+                top + STATS_BOX_PADDING + i as i32 * line_height,
+            ),
+            font.clone(),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Visualizes the scatter plot with the regression line described by `fit`.
+/// The output is an SVG when `file_name` ends in `.svg` and a PNG bitmap otherwise.
+pub fn visualize_relationship(
+    data: &[(f64, f64)],
+    fit: &RegressionResult,
+    options: &PlotOptions,
+    style: &ChartStyle,
+    x_label: &str,
+    y_label: &str,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let is_svg = Path::new(file_name)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+
+    let drawn = if is_svg {
+        let root = SVGBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+        draw_with_font_fallback(&root, data, fit, options, style, x_label, y_label)
+    } else {
+        let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+        draw_with_font_fallback(&root, data, fit, options, style, x_label, y_label)
+    };
+    drawn.map_err(|e| format!("cannot draw {}: {}", file_name, e))?;
+
+    info!("Scatter plot saved to {}", file_name);
+    Ok(())
+}
+
+/// Likely cause and fix of a font loading failure, appended to the error message.
+const FONT_HINT: &str = "plotters could not load a system font, which is common on headless \
+servers; install a font package such as fonts-dejavu-core along with fontconfig";
+
+/// Whether `e` is a plotters drawing error caused by loading a font. plotters does not
+/// re-export the inner error type, so its font variant is recognized by its message.
+fn is_font_error<DB: DrawingBackend>(e: &(dyn Error + 'static)) -> bool
+where
+    DB::ErrorType: 'static,
+{
+    matches!(
+        e.downcast_ref::<DrawingAreaErrorKind<DB::ErrorType>>(),
+        Some(DrawingAreaErrorKind::BackendError(inner))
+            if inner.to_string().starts_with("Font loading error")
+    )
+}
+
+/// Draws the full scatter plot, or, if a font cannot be loaded, warns and falls back to
+/// `draw_plain_relationship` so that a plot is still produced. Fails with `FONT_HINT` if
+/// even the plain plot cannot be drawn.
+fn draw_with_font_fallback<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &[(f64, f64)],
+    fit: &RegressionResult,
+    options: &PlotOptions,
+    style: &ChartStyle,
+    x_label: &str,
+    y_label: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    match draw_relationship(root, data, fit, options, style, x_label, y_label) {
+        Err(e) if is_font_error::<DB>(e.as_ref()) => {
+            status!(
+                "Warning: {} ({}); drawing '{}' without any text",
+                e,
+                FONT_HINT,
+                fit.name
+            );
+            draw_plain_relationship(root, data, fit, options, style)
+                .map_err(|e| format!("{} ({})", e, FONT_HINT).into())
+        }
+        result => result,
+    }
+}
+
+/// Draws only the points and the regression line, with no caption, axis labels, or legend,
+/// for when no font is available to render text. Honors the margin, point size and opacity
+/// of `style` and the log scale of `options`, like the full plot.
+fn draw_plain_relationship<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &[(f64, f64)],
+    fit: &RegressionResult,
+    options: &PlotOptions,
+    style: &ChartStyle,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let log_scale = options.log_scale;
+    let points: Vec<(f64, f64)> = data
+        .iter()
+        .copied()
+        .filter(|(x, y)| !log_scale || (*x > 0.0 && *y > 0.0))
+        .collect();
+    let (max_x, max_y) = chart_bounds(&points)?;
+    let min_x = points.iter().map(|(x, _)| *x).fold(max_x, f64::min);
+    let line = fit_line_points(
+        fit.slope,
+        fit.intercept,
+        min_x,
+        max_x,
+        log_scale,
+        style.line_samples,
+    );
+    let mut builder = ChartBuilder::on(root);
+    builder.margin(style.margin);
+
+    if log_scale {
+        let min_y = points.iter().map(|(_, y)| *y).fold(max_y, f64::min);
+        let mut chart =
+            builder.build_cartesian_2d((min_x..max_x).log_scale(), (min_y..max_y).log_scale())?;
+        let runs = clip_to_y_range(&line, min_y, max_y);
+        draw_plain_series(&mut chart, &points, runs, options.colors, style)?;
+    } else {
+        let min_y = points.iter().map(|(_, y)| *y).fold(0.0, f64::min);
+        let (x_start, x_end) = padded_range(min_x, max_x);
+        let (y_start, y_end) = padded_range(min_y, max_y);
+        let mut chart = builder.build_cartesian_2d(x_start..x_end, y_start..y_end)?;
+        let runs = clip_to_y_range(&line, y_start, y_end);
+        draw_plain_series(&mut chart, &points, runs, options.colors, style)?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+/// Draws the points and the runs of the fitted line of `draw_plain_relationship`.
+fn draw_plain_series<'a, DB, CT>(
+    chart: &mut ChartContext<'a, DB, CT>,
+    points: &[(f64, f64)],
+    runs: Vec<Vec<(f64, f64)>>,
+    colors: SeriesColors,
+    style: &ChartStyle,
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend + 'a,
+    DB::ErrorType: 'static,
+    CT: CoordTranslate<From = (f64, f64)>,
+{
+    chart.draw_series(points.iter().map(|point| {
+        Circle::new(
+            *point,
+            style.point_radius,
+            colors.point.mix(style.point_alpha).filled(),
+        )
+    }))?;
+    chart.draw_series(
+        runs.into_iter()
+            .map(|run| PathElement::new(run, colors.line)),
+    )?;
+    Ok(())
+}
+
+/// Number of x positions sampled when drawing a curve.
+const CURVE_SAMPLES: usize = 200;
+
+/// Points of the line `y = slope * x + intercept` spanning exactly `min_x..=max_x`.
+/// The two endpoints suffice on linear axes; on logarithmic axes the line is curved, so it
+/// is sampled at `samples + 1` geometrically spaced x values instead.
+fn fit_line_points(
+    slope: f64,
+    intercept: f64,
+    min_x: f64,
+    max_x: f64,
+    log_scale: bool,
+    samples: usize,
+) -> Vec<(f64, f64)> {
+    if !log_scale || min_x <= 0.0 {
+        return vec![
+            (min_x, predict(slope, intercept, min_x)),
+            (max_x, predict(slope, intercept, max_x)),
+        ];
+    }
+    let ratio = max_x / min_x;
+    (0..=samples)
+        .map(|i| {
+            let x = min_x * ratio.powf(i as f64 / samples as f64);
+            (x, predict(slope, intercept, x))
+        })
+        .collect()
+}
+
+/// Points of the curve `f` at `samples + 1` evenly spaced x values spanning `min_x..=max_x`.
+fn curve_points(min_x: f64, max_x: f64, samples: usize, f: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+    (0..=samples)
+        .map(|i| {
+            let x = min_x + (max_x - min_x) * i as f64 / samples as f64;
+            (x, f(x))
+        })
+        .collect()
+}
+
+/// Splits a polyline into the runs that lie within `y_min..=y_max`, ending each run where
+/// it crosses a bound (found by linear interpolation) so no segment leaves the range.
+fn clip_to_y_range(points: &[(f64, f64)], y_min: f64, y_max: f64) -> Vec<Vec<(f64, f64)>> {
+    let inside = |y: f64| (y_min..=y_max).contains(&y);
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        if i > 0 {
+            let (px, py) = points[i - 1];
+            let mut crossings: Vec<(f64, f64)> = [y_min, y_max]
+                .into_iter()
+                .filter(|bound| (py - bound) * (y - bound) < 0.0)
+                .map(|bound| ((bound - py) / (y - py), bound))
+                .collect();
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            run.extend(
+                crossings
+                    .into_iter()
+                    .map(|(t, bound)| (px + t * (x - px), bound)),
+            );
+        }
+        if inside(y) {
+            run.push((x, y));
+        } else if !run.is_empty() {
+            runs.push(std::mem::take(&mut run));
+        }
+    }
+    runs.push(run);
+    runs.retain(|run| run.len() > 1);
+    runs
+}
+
+/// Draws the scatter plot and regression line onto any plotters backend.
+fn draw_relationship<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &[(f64, f64)],
+    fit: &RegressionResult,
+    options: &PlotOptions,
+    style: &ChartStyle,
+    x_label: &str,
+    y_label: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let RegressionResult {
+        name: title,
+        slope,
+        intercept,
+        r_squared,
+        ..
+    } = fit;
+    let log_scale = options.log_scale;
+
+    root.fill(&WHITE)?;
+
+    let mut is_outlier = vec![false; data.len()];
+    for &i in &options.outliers {
+        is_outlier[i] = true;
+    }
+
+    let is_positive = |(x, y): (f64, f64)| x > 0.0 && y > 0.0;
+    let mut points: Vec<((f64, f64), bool)> = data.iter().copied().zip(is_outlier).collect();
+    let mut labels = options.labels.clone();
+    if log_scale {
+        points.retain(|(point, _)| is_positive(*point));
+        labels.retain(|(point, _)| is_positive(*point));
+        let omitted = data.len() - points.len();
+        if omitted > 0 {
+            status!(
+                "Warning: omitting {} points with non-positive values from log-scale plot '{}'",
+                omitted,
+                title
+            );
+        }
+    }
+
+    let plotted: Vec<(f64, f64)> = points.iter().map(|(point, _)| *point).collect();
+    let (max_x, max_y) = chart_bounds(&plotted)?;
+    let min_x = plotted.iter().map(|(x, _)| *x).fold(max_x, f64::min);
+
+    let samples = style.line_samples;
+    let mut lines = vec![(
+        fit_line_points(*slope, *intercept, min_x, max_x, log_scale, samples),
+        options.colors.line,
+        format!(
+            "y = {:.2}x + {:.2} (R² = {:.4})",
+            slope, intercept, r_squared
+        ),
+    )];
+
+    if let Some(coefficients) = &options.polynomial {
+        let curve = curve_points(min_x, max_x, samples, |x| {
+            evaluate_polynomial(coefficients, x)
+        });
+        lines.push((curve, MAGENTA, format_polynomial(coefficients)));
+    }
+
+    if let Some((robust_slope, robust_intercept)) = options.robust {
+        lines.push((
+            fit_line_points(
+                robust_slope,
+                robust_intercept,
+                min_x,
+                max_x,
+                log_scale,
+                samples,
+            ),
+            CYAN,
+            format!(
+                "Theil-Sen: y = {:.2}x + {:.2}",
+                robust_slope, robust_intercept
+            ),
+        ));
+    }
+
+    if let Some(origin_slope) = options.origin_slope {
+        lines.push((
+            fit_line_points(origin_slope, 0.0, min_x, max_x, log_scale, samples),
+            GRAY,
+            format!("Through origin: y = {:.2}x", origin_slope),
+        ));
+    }
+
+    if let Some(smoothed) = &options.smoothed {
+        let smoothed = smoothed
+            .iter()
+            .copied()
+            .filter(|point| !log_scale || is_positive(*point))
+            .collect();
+        lines.push((smoothed, DARK_GREEN, "Moving average".to_string()));
+    }
+
+    if let Some((log_slope, log_intercept)) = options.exponential {
+        let curve = curve_points(min_x, max_x, samples, |x| {
+            predict(log_slope, log_intercept, x).exp()
+        });
+        lines.push((
+            curve,
+            GREEN,
+            format!("y = {:.2} e^({:.3e}x)", log_intercept.exp(), log_slope),
+        ));
+    }
+
+    let clip_lines = |y_min: f64, y_max: f64| -> Vec<FitLine> {
+        lines
+            .iter()
+            .map(|(points, color, legend)| {
+                (
+                    clip_to_y_range(points, y_min, y_max),
+                    *color,
+                    legend.clone(),
+                )
+            })
+            .collect()
+    };
+
+    let mut builder = ChartBuilder::on(root);
+    builder
+        .caption(
+            options.title.as_deref().unwrap_or(title),
+            ("sans-serif", style.caption_font_size),
+        )
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40);
+
+    let highlight = options
+        .highlight
+        .filter(|point| !log_scale || is_positive(*point));
+
+    let plot_area = if log_scale {
+        let min_y = plotted.iter().map(|(_, y)| *y).fold(max_y, f64::min);
+        let mut chart =
+            builder.build_cartesian_2d((min_x..max_x).log_scale(), (min_y..max_y).log_scale())?;
+        let mut mesh = chart.configure_mesh();
+        style.mesh.apply(&mut mesh);
+        mesh.x_desc(x_label).y_desc(y_label).draw()?;
+        if let Some(band) = &options.confidence_band {
+            draw_band(&mut chart, band, min_y, max_y, options.colors.line)?;
+        }
+        draw_fit(
+            &mut chart,
+            &points,
+            &labels,
+            clip_lines(min_y, max_y),
+            highlight,
+            options.colors.point,
+            style,
+        )?;
+        chart.plotting_area().get_pixel_range()
+    } else {
+        let data_min_y = plotted.iter().map(|(_, y)| *y).fold(0.0, f64::min);
+        let (x_start, x_end) = padded_range(min_x, max_x);
+        let (y_start, y_end) = padded_range(data_min_y, max_y);
+        let mut chart = builder.build_cartesian_2d(x_start..x_end, y_start..y_end)?;
+        let mut mesh = chart.configure_mesh();
+        style.mesh.apply(&mut mesh);
+        mesh.x_desc(x_label).y_desc(y_label).draw()?;
+        if let Some(band) = &options.confidence_band {
+            draw_band(&mut chart, band, y_start, y_end, options.colors.line)?;
+        }
+        draw_fit(
+            &mut chart,
+            &points,
+            &labels,
+            clip_lines(y_start, y_end),
+            highlight,
+            options.colors.point,
+            style,
+        )?;
+        chart.plotting_area().get_pixel_range()
+    };
+
+    if let Some(stats) = &options.stats_box {
+        draw_stats_box(root, plot_area, &stats.lines(), options.stats_corner)?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+/// A fitted line or curve given as the runs of points it is drawn with (see
+/// `clip_to_y_range`), its color, and legend label.
+type FitLine = (Vec<Vec<(f64, f64)>>, RGBColor, String);
+
+/// Pixel offset of a point label from its point, up and to the right of the marker.
+const LABEL_OFFSET: (i32, i32) = (6, -16);
+
+/// Font size of point labels.
+const LABEL_FONT_SIZE: u32 = 15;
+
+/// Pixel radius of the star marking a highlighted prediction.
+const HIGHLIGHT_RADIUS: f64 = 12.0;
+
+/// Vertices of a five-pointed star with outer radius `radius`, as pixel offsets from
+/// its center.
+fn star_vertices(radius: f64) -> Vec<(i32, i32)> {
+    (0..10)
+        .map(|i| {
+            let r = if i % 2 == 0 { radius } else { radius * 0.4 };
+            let angle = std::f64::consts::PI * (i as f64 / 5.0 - 0.5);
+            (
+                (r * angle.cos()).round() as i32,
+                (r * angle.sin()).round() as i32,
+            )
+        })
+        .collect()
+}
+
+/// Formats a value compactly with a K, M or B suffix, e.g. `1.2M` for 1,234,567.
+pub fn format_compact(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude >= 1e9 {
+        format!("{:.1}B", value / 1e9)
+    } else if magnitude >= 1e6 {
+        format!("{:.1}M", value / 1e6)
+    } else if magnitude >= 1e3 {
+        format!("{:.1}K", value / 1e3)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Opacity of the shaded confidence band.
+const BAND_ALPHA: f64 = 0.2;
+
+/// Shades a confidence band given as `(x, lower, upper)` in a translucent `color`, with its
+/// bounds clamped to the y range of the chart.
+fn draw_band<'a, DB, CT>(
+    chart: &mut ChartContext<'a, DB, CT>,
+    band: &[(f64, f64, f64)],
+    y_min: f64,
+    y_max: f64,
+    color: RGBColor,
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend + 'a,
+    DB::ErrorType: 'static,
+    CT: CoordTranslate<From = (f64, f64)>,
+{
+    let clamp = |y: f64| y.clamp(y_min, y_max);
+    let outline: Vec<(f64, f64)> = band
+        .iter()
+        .map(|(x, _, upper)| (*x, clamp(*upper)))
+        .chain(band.iter().rev().map(|(x, lower, _)| (*x, clamp(*lower))))
+        .collect();
+    chart
+        .draw_series(std::iter::once(Polygon::new(
+            outline,
+            color.mix(BAND_ALPHA).filled(),
+        )))?
+        .label("95% confidence band")
+        .legend(move |(x, y)| {
+            Rectangle::new(
+                [(x, y - 5), (x + 20, y + 5)],
+                color.mix(BAND_ALPHA).filled(),
+            )
+        });
+    Ok(())
+}
+
+/// Draws the points, labels, fitted lines, and legend onto a chart with any coordinate system.
+fn draw_fit<'a, DB, CT>(
+    chart: &mut ChartContext<'a, DB, CT>,
+    points: &[((f64, f64), bool)],
+    labels: &[((f64, f64), String)],
+    lines: Vec<FitLine>,
+    highlight: Option<(f64, f64)>,
+    point_color: RGBColor,
+    style: &ChartStyle,
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend + 'a,
+    DB::ErrorType: 'static,
+    CT: CoordTranslate<From = (f64, f64)>,
+{
+    chart.draw_series(points.iter().map(|((x, y), outlier)| {
+        let color = if *outlier { ORANGE } else { point_color };
+        Circle::new(
+            (*x, *y),
+            style.point_radius,
+            color.mix(style.point_alpha).filled(),
+        )
+    }))?;
+
+    chart.draw_series(labels.iter().map(|(point, name)| {
+        EmptyElement::at(*point)
+            + Text::new(
+                name.clone(),
+                LABEL_OFFSET,
+                ("sans-serif", LABEL_FONT_SIZE).into_font(),
+            )
+    }))?;
+
+    for (runs, color, legend) in lines {
+        chart
+            .draw_series(runs.into_iter().map(|run| PathElement::new(run, color)))?
+            .label(legend)
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color));
+    }
+
+    if let Some(point) = highlight {
+        chart.draw_series(std::iter::once(
+            EmptyElement::at(point)
+                + Polygon::new(star_vertices(HIGHLIGHT_RADIUS), BLACK.filled())
+                + Text::new(
+                    format!("predicted: {}", format_compact(point.1)),
+                    (HIGHLIGHT_RADIUS as i32 + 4, -(HIGHLIGHT_RADIUS as i32)),
+                    ("sans-serif", 18).into_font(),
+                ),
+        ))?;
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+/// Colors assigned in order to the series of a combined plot.
+const SERIES_COLORS: [RGBColor; 5] = [RED, GREEN, BLUE, MAGENTA, CYAN];
+
+/// A relationship on a combined plot, given as its name, points, slope, and intercept.
+pub type CombinedSeries<'a> = (&'a str, Vec<(f64, f64)>, f64, f64);
+
+/// Visualizes several relationships on one chart. Every series gets its own color
+/// for both points and regression line.
+pub fn visualize_combined(
+    relationships: &[CombinedSeries],
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    draw_series_chart(
+        relationships,
+        &SERIES_COLORS,
+        "Total Streams vs All Predictors",
+        ("Predictor Streams", "Total Streams"),
+        style,
+        file_name,
+    )?;
+    info!("Combined plot saved to {}", file_name);
+    Ok(())
+}
+
+/// Shades of blue, lightest first, given to the quartiles from the smallest artists up.
+const QUARTILE_SHADES: [RGBColor; 4] = [
+    RGBColor(158, 202, 225),
+    RGBColor(66, 146, 198),
+    RGBColor(33, 113, 181),
+    RGBColor(8, 48, 107),
+];
+
+/// Visualizes the separate fits of each quartile of a relationship on one chart, with the
+/// points and line of every quartile in a darker shade than the one below it.
+pub fn visualize_quartiles(
+    quartiles: &[CombinedSeries],
+    title: &str,
+    x_label: &str,
+    y_label: &str,
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    draw_series_chart(
+        quartiles,
+        &QUARTILE_SHADES,
+        title,
+        (x_label, y_label),
+        style,
+        file_name,
+    )?;
+    info!("Quartile plot saved to {}", file_name);
+    Ok(())
+}
+
+/// Draws the points and regression line of every series in `colors`, cycling through them,
+/// with a legend in the upper left.
+fn draw_series_chart(
+    relationships: &[CombinedSeries],
+    colors: &[RGBColor],
+    title: &str,
+    (x_desc, y_desc): (&str, &str),
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let all_points: Vec<(f64, f64)> = relationships
+        .iter()
+        .flat_map(|(_, points, _, _)| points.iter().copied())
+        .collect();
+    let (max_x, max_y) = chart_bounds(&all_points)?;
+    let min_x = all_points.iter().map(|(x, _)| *x).fold(max_x, f64::min);
+    let min_y = all_points.iter().map(|(_, y)| *y).fold(max_y, f64::min);
+    let (x_start, x_end) = padded_range(min_x, max_x);
+    let (y_start, y_end) = padded_range(min_y, max_y);
+
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", style.caption_font_size))
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40)
+        .build_cartesian_2d(x_start..x_end, y_start..y_end)?;
+
+    let mut mesh = chart.configure_mesh();
+    style.mesh.apply(&mut mesh);
+    mesh.x_desc(x_desc).y_desc(y_desc).draw()?;
+
+    for (i, (name, points, slope, intercept)) in relationships.iter().enumerate() {
+        let color = colors[i % colors.len()];
+        let (min_x, max_x) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| {
+                (lo.min(*x), hi.max(*x))
+            });
+
+        chart
+            .draw_series(
+                points
+                    .iter()
+                    .map(|(x, y)| Circle::new((*x, *y), 3, color.mix(0.5).filled())),
+            )?
+            .label(*name)
+            .legend(move |(x, y)| Circle::new((x + 10, y), 4, color.filled()));
+
+        chart
+            .draw_series(LineSeries::new(
+                fit_line_points(*slope, *intercept, min_x, max_x, false, style.line_samples),
+                color.stroke_width(2),
+            ))?
+            .label(format!("y = {:.2}x + {:.2}", slope, intercept))
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
+
+/// Calculates the residual (observed y minus predicted y) of each point.
+/// Fails on empty data.
+pub fn compute_residuals(
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+) -> Result<Vec<f64>, StatsError> {
+    if data.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    Ok(data
+        .iter()
+        .map(|(x, y)| y - (slope * x + intercept))
+        .collect())
+}
+
+/// Visualizes the residuals against x with a horizontal zero line, in the palette colors
+/// of the relationship `key`. Fails if there is not exactly one residual per point.
+pub fn visualize_residuals(
+    data: &[(f64, f64)],
+    residuals: &[f64],
+    key: &str,
+    title: &str,
+    x_label: &str,
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    if data.len() != residuals.len() {
+        return Err(StatsError::LengthMismatch {
+            expected: data.len(),
+            found: residuals.len(),
+        }
+        .into());
+    }
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let (min_x, max_x) = data
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| {
+            (lo.min(*x), hi.max(*x))
+        });
+    let (x_start, x_end) = padded_range(min_x, max_x);
+    let max_abs = residuals.iter().map(|r| r.abs()).fold(0.0, f64::max);
+    let y_extent = if max_abs > 0.0 {
+        max_abs * (1.0 + AXIS_PADDING)
+    } else {
+        1.0
+    };
+    let colors = style.palette.colors(key);
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", style.caption_font_size))
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40)
+        .build_cartesian_2d(x_start..x_end, -y_extent..y_extent)?;
+
+    let mut mesh = chart.configure_mesh();
+    style.mesh.apply(&mut mesh);
+    mesh.x_desc(x_label).y_desc("Residual").draw()?;
+
+    chart.draw_series(data.iter().zip(residuals).map(|((x, _), r)| {
+        Circle::new(
+            (*x, *r),
+            style.point_radius,
+            colors.point.mix(style.point_alpha).filled(),
+        )
+    }))?;
+
+    chart.draw_series(LineSeries::new(
+        vec![(x_start, 0.0), (x_end, 0.0)],
+        colors.line.stroke_width(2),
+    ))?;
+
+    root.present()?;
+    info!("Residual plot saved to {}", file_name);
+    Ok(())
+}
+
+/// A point's hex cell in grid units (one unit per column): its row on the square lattice
+/// of cell centers, or on the same lattice shifted by half a cell in both directions.
+fn hex_cell(u: f64, v: f64) -> (bool, i64, i64) {
+    let row_height = 3f64.sqrt();
+    let (i, j) = (u.round(), (v / row_height).round());
+    let (si, sj) = ((u - 0.5).round(), (v / row_height - 0.5).round());
+    let distance = |cu: f64, cv: f64| (u - cu).powi(2) + (v - cv).powi(2);
+    if distance(i, j * row_height) <= distance(si + 0.5, (sj + 0.5) * row_height) {
+        (false, i as i64, j as i64)
+    } else {
+        (true, si as i64, sj as i64)
+    }
+}
+
+/// Aggregates points into a grid of pointy-top hexagons, `bins` columns across the x range
+/// and hexagons of the same shape (in axis units) vertically. Returns the center of each
+/// non-empty cell with its count, sorted by center.
+pub fn hexbin_counts(data: &[(f64, f64)], bins: usize) -> Vec<((f64, f64), usize)> {
+    if data.is_empty() || bins == 0 {
+        return Vec::new();
+    }
+    let (min_x, max_x, min_y, max_y) = data.iter().fold(
+        (
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(lo_x, hi_x, lo_y, hi_y), (x, y)| (lo_x.min(*x), hi_x.max(*x), lo_y.min(*y), hi_y.max(*y)),
+    );
+    let cell_width = if max_x > min_x {
+        (max_x - min_x) / bins as f64
+    } else {
+        1.0
+    };
+    let cell_height = if max_y > min_y {
+        (max_y - min_y) / bins as f64
+    } else {
+        1.0
+    };
+
+    let mut counts: HashMap<(bool, i64, i64), usize> = HashMap::new();
+    for (x, y) in data {
+        let cell = hex_cell((x - min_x) / cell_width, (y - min_y) / cell_height);
+        *counts.entry(cell).or_insert(0) += 1;
+    }
+
+    let row_height = 3f64.sqrt();
+    let mut cells: Vec<((f64, f64), usize)> = counts
+        .into_iter()
+        .map(|((shifted, i, j), count)| {
+            let offset = if shifted { 0.5 } else { 0.0 };
+            let u = i as f64 + offset;
+            let v = (j as f64 + offset) * row_height;
+            ((min_x + u * cell_width, min_y + v * cell_height), count)
+        })
+        .collect();
+    cells.sort_by(|a, b| a.0 .0.total_cmp(&b.0 .0).then(a.0 .1.total_cmp(&b.0 .1)));
+    cells
+}
+
+/// Light and dark ends of the hexbin color gradient.
+const HEXBIN_LOW: RGBColor = RGBColor(255, 237, 160);
+const HEXBIN_HIGH: RGBColor = RGBColor(189, 0, 38);
+
+/// Color of a hexbin cell, on a log scale so that sparse cells stay distinguishable
+/// next to the dense cells near the origin.
+fn hexbin_color(count: usize, max_count: usize) -> RGBColor {
+    let t = if max_count > 1 {
+        (count as f64).ln() / (max_count as f64).ln()
+    } else {
+        1.0
+    };
+    let channel = |low: u8, high: u8| (low as f64 + (high as f64 - low as f64) * t).round() as u8;
+    RGBColor(
+        channel(HEXBIN_LOW.0, HEXBIN_HIGH.0),
+        channel(HEXBIN_LOW.1, HEXBIN_HIGH.1),
+        channel(HEXBIN_LOW.2, HEXBIN_HIGH.2),
+    )
+}
+
+/// Visualizes the density of `data` as a hexbin plot with `bins` hexagons across the
+/// x range, each colored by its point count. Suits overplotted scatter data.
+pub fn visualize_hexbin(
+    data: &[(f64, f64)],
+    bins: usize,
+    title: &str,
+    x_label: &str,
+    y_label: &str,
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let cells = hexbin_counts(data, bins);
+    if cells.is_empty() {
+        return Err("hexbin plot needs at least one point and one bin".into());
+    }
+    let max_count = cells.iter().map(|(_, count)| *count).max().unwrap_or(1);
+
+    let (min_x, max_x, min_y, max_y) = data.iter().fold(
+        (
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(lo_x, hi_x, lo_y, hi_y), (x, y)| (lo_x.min(*x), hi_x.max(*x), lo_y.min(*y), hi_y.max(*y)),
+    );
+    let cell_width = if max_x > min_x {
+        (max_x - min_x) / bins as f64
+    } else {
+        1.0
+    };
+    let cell_height = if max_y > min_y {
+        (max_y - min_y) / bins as f64
+    } else {
+        1.0
+    };
+    let x_range = padded_range(min_x - cell_width, max_x + cell_width);
+    let y_range = padded_range(min_y - cell_height, max_y + cell_height);
+    // Circumradius of a hexagon whose flat sides are one column apart.
+    let radius = 1.0 / 3f64.sqrt();
+
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", style.caption_font_size))
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40)
+        .build_cartesian_2d(x_range.0..x_range.1, y_range.0..y_range.1)?;
+
+    let mut mesh = chart.configure_mesh();
+    style.mesh.apply(&mut mesh);
+    mesh.x_desc(x_label).y_desc(y_label).draw()?;
+
+    chart.draw_series(cells.iter().map(|((cx, cy), count)| {
+        let vertices: Vec<(f64, f64)> = (0..6)
+            .map(|k| {
+                let angle = (30.0 + 60.0 * k as f64).to_radians();
+                (
+                    cx + radius * angle.cos() * cell_width,
+                    cy + radius * angle.sin() * cell_height,
+                )
+            })
+            .collect();
+        Polygon::new(vertices, hexbin_color(*count, max_count).filled())
+    }))?;
+
+    for count in [1, max_count] {
+        let color = hexbin_color(count, max_count);
+        chart
+            .draw_series(std::iter::empty::<Circle<(f64, f64), i32>>())?
+            .label(format!(
+                "{} {}",
+                count,
+                if count == 1 { "point" } else { "points" }
+            ))
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled()));
+    }
+
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    info!("Hexbin plot saved to {}", file_name);
+    Ok(())
+}
+
+/// Buckets `values` into `bins` equal-width bins spanning their min and max.
+/// Returns the lower edge of the first bin, the bin width, and the count in each bin.
+pub fn histogram_counts(values: &[f64], bins: usize) -> (f64, f64, Vec<usize>) {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+
+    for value in values {
+        let bin = if width > 0.0 {
+            (((value - min) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+
+    (min, width, counts)
+}
+
+/// Visualizes the distribution of `values` as a histogram with a count above each bar.
+pub fn visualize_histogram(
+    values: &[f64],
+    bins: usize,
+    title: &str,
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    if values.is_empty() || bins == 0 {
+        return Err("histogram needs at least one value and one bin".into());
+    }
+
+    let (min, width, counts) = histogram_counts(values, bins);
+    let width = if width > 0.0 { width } else { 1.0 };
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, ("sans-serif", style.caption_font_size))
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40)
+        .build_cartesian_2d(
+            min..min + width * bins as f64,
+            0usize..max_count + max_count / 10 + 1,
+        )?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Value")
+        .y_desc("Count")
+        .draw()?;
+
+    chart.draw_series(counts.iter().enumerate().map(|(i, count)| {
+        let left = min + width * i as f64;
+        Rectangle::new([(left, 0), (left + width, *count)], BLUE.mix(0.6).filled())
+    }))?;
+
+    chart.draw_series(counts.iter().enumerate().map(|(i, count)| {
+        let center = min + width * (i as f64 + 0.5);
+        EmptyElement::at((center, *count))
+            + Text::new(count.to_string(), (-8, -18), ("sans-serif", 15).into_font())
+    }))?;
+
+    root.present()?;
+    info!("Histogram saved to {}", file_name);
+    Ok(())
+}
+
+/// Calculates the quantile `q` (in [0, 1]) of an ascending slice by linear interpolation
+/// between the two nearest ranks (`(n - 1) * q`), matching R's default type 7 method.
+pub fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    let position = (sorted.len() - 1) as f64 * q;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Calculates the `p`th percentile (in [0, 100]) of `values` with the interpolation of
+/// `quantile_sorted`, working on a sorted copy so the caller's slice is left untouched.
+/// Fails if `values` is empty or `p` lies outside [0, 100].
+pub fn percentile(values: &[f64], p: f64) -> Result<f64, StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::EmptyInput);
+    }
+    if !(0.0..=100.0).contains(&p) {
+        return Err(StatsError::PercentileOutOfRange(p));
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    Ok(quantile_sorted(&sorted, p / 100.0))
+}
+
+/// Box plot statistics of a single series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxStats {
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    /// Most extreme values within 1.5 * IQR of the box.
+    pub whisker_low: f64,
+    pub whisker_high: f64,
+    /// Values beyond the whiskers.
+    pub outliers: Vec<f64>,
+}
+
+/// Computes box plot statistics with quartiles from `quantile_sorted`.
+/// Returns `None` for an empty series.
+pub fn box_stats(values: &[f64]) -> Option<BoxStats> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let q1 = quantile_sorted(&sorted, 0.25);
+    let median = quantile_sorted(&sorted, 0.5);
+    let q3 = quantile_sorted(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let (low_fence, high_fence) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+
+    let inside = |v: &&f64| **v >= low_fence && **v <= high_fence;
+    Some(BoxStats {
+        q1,
+        median,
+        q3,
+        whisker_low: *sorted.iter().find(inside).unwrap_or(&q1),
+        whisker_high: *sorted.iter().rev().find(inside).unwrap_or(&q3),
+        outliers: sorted.iter().copied().filter(|v| !inside(&v)).collect(),
+    })
+}
+
+/// Visualizes each named series as a box with 1.5 * IQR whiskers and individual outlier points.
+pub fn visualize_boxplot(
+    series: &[(&str, Vec<f64>)],
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let stats: Vec<(&str, BoxStats)> = series
+        .iter()
+        .filter_map(|(name, values)| box_stats(values).map(|stats| (*name, stats)))
+        .collect();
+    if stats.is_empty() {
+        return Err("box plot needs at least one non-empty series".into());
+    }
+
+    let max_y = stats
+        .iter()
+        .flat_map(|(_, s)| s.outliers.iter().copied().chain([s.whisker_high]))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let min_y = stats
+        .iter()
+        .flat_map(|(_, s)| s.outliers.iter().copied().chain([s.whisker_low]))
+        .fold(f64::INFINITY, f64::min);
+
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            "Stream Distributions",
+            ("sans-serif", style.caption_font_size),
+        )
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(40)
+        .build_cartesian_2d(0.0..stats.len() as f64, min_y.min(0.0)..max_y)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(stats.len() * 2 + 1)
+        .x_label_formatter(&|x| {
+            let i = x.floor() as usize;
+            if x.fract() == 0.5 && i < stats.len() {
+                stats[i].0.to_string()
+            } else {
+                String::new()
+            }
+        })
+        .y_desc("Streams")
+        .draw()?;
+
+    for (i, (_, s)) in stats.iter().enumerate() {
+        let center = i as f64 + 0.5;
+        let (left, right) = (center - 0.25, center + 0.25);
+
+        chart.draw_series([
+            Rectangle::new([(left, s.q1), (right, s.q3)], BLUE.mix(0.3).filled()),
+            Rectangle::new([(left, s.q1), (right, s.q3)], BLUE.stroke_width(2)),
+        ])?;
+        chart.draw_series([
+            PathElement::new([(left, s.median), (right, s.median)], RED.stroke_width(2)),
+            PathElement::new([(center, s.q3), (center, s.whisker_high)], BLACK),
+            PathElement::new([(center, s.q1), (center, s.whisker_low)], BLACK),
+            PathElement::new(
+                [
+                    (center - 0.1, s.whisker_high),
+                    (center + 0.1, s.whisker_high),
+                ],
+                BLACK,
+            ),
+            PathElement::new(
+                [(center - 0.1, s.whisker_low), (center + 0.1, s.whisker_low)],
+                BLACK,
+            ),
+        ])?;
+        chart.draw_series(
+            s.outliers
+                .iter()
+                .map(|y| Circle::new((center, *y), 3, ORANGE.filled())),
+        )?;
+    }
+
+    root.present()?;
+    info!("Box plot saved to {}", file_name);
+    Ok(())
+}
+
+/// Draws one labeled bar per relationship whose height is its slope, with the slope written
+/// above (or, when negative, below) the bar.
+pub fn visualize_slope_comparison(
+    results: &[(&str, f64)],
+    style: &ChartStyle,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    if results.is_empty() {
+        return Err("slope comparison needs at least one relationship".into());
+    }
+    let min_slope = results.iter().map(|(_, s)| *s).fold(0.0, f64::min);
+    let max_slope = results.iter().map(|(_, s)| *s).fold(0.0, f64::max);
+    let (y_start, y_end) = padded_range(min_slope, max_slope);
+
+    let root = BitMapBackend::new(file_name, (style.width, style.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(
+            "Regression Slopes by Relationship",
+            ("sans-serif", style.caption_font_size),
+        )
+        .margin(style.margin)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..results.len() as f64, y_start..y_end)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(results.len() * 2 + 1)
+        .x_label_formatter(&|x| {
+            let i = x.floor() as usize;
+            if x.fract() == 0.5 && i < results.len() {
+                results[i].0.to_string()
+            } else {
+                String::new()
+            }
+        })
+        .y_desc("Slope")
+        .draw()?;
+
+    for (i, (_, slope)) in results.iter().enumerate() {
+        let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+        let center = i as f64 + 0.5;
+        chart.draw_series([Rectangle::new(
+            [(center - 0.3, 0.0), (center + 0.3, *slope)],
+            color.mix(0.7).filled(),
+        )])?;
+        let offset = if *slope < 0.0 { 6 } else { -22 };
+        chart.draw_series(std::iter::once(
+            EmptyElement::at((center, *slope))
+                + Text::new(
+                    format!("{:.4}", slope),
+                    (-25, offset),
+                    ("sans-serif", 18).into_font(),
+                ),
+        ))?;
+    }
+
+    root.present()?;
+    info!("Slope comparison saved to {}", file_name);
+    Ok(())
+}
+
+/// Fitted statistics for a single relationship.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegressionResult {
+    pub name: String,
+    pub slope: f64,
+    /// Slope in standard deviations of y per standard deviation of x, when both vary.
+    pub standardized_slope: Option<f64>,
+    pub intercept: f64,
+    pub r_squared: f64,
+    pub correlation: f64,
+    /// F-statistic of the fit, when there are enough points to compute it.
+    pub f_statistic: Option<f64>,
+    /// Approximate p-value of the F-test.
+    pub p_value: Option<f64>,
+}
+
+/// Writes one CSV row per regression result, overwriting any existing file at `path`.
+pub fn write_report(path: &str, results: &[RegressionResult]) -> Result<(), Box<dyn Error>> {
+    let mut writer = Writer::from_path(path)?;
+    writer.write_record([
+        "relationship",
+        "slope",
+        "standardized_slope",
+        "intercept",
+        "r_squared",
+        "correlation",
+        "f_statistic",
+        "p_value",
+    ])?;
+
+    let optional = |value: Option<f64>| value.map_or_else(String::new, |v| v.to_string());
+    for result in results {
+        writer.write_record([
+            result.name.clone(),
+            result.slope.to_string(),
+            optional(result.standardized_slope),
+            result.intercept.to_string(),
+            result.r_squared.to_string(),
+            result.correlation.to_string(),
+            optional(result.f_statistic),
+            optional(result.p_value),
+        ])?;
+    }
+
+    writer.flush()?;
+    info!("Regression report saved to {}", path);
+    Ok(())
+}
+
+/// Writes one CSV row per point with the artist name, actual and predicted total streams,
+/// and residual (actual minus predicted) under the line `y = slope * x + intercept`,
+/// overwriting any existing file at `path`. Fails if there is not one name per point.
+pub fn write_predictions(
+    path: &str,
+    data: &[(f64, f64)],
+    slope: f64,
+    intercept: f64,
+    names: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    if data.len() != names.len() {
+        return Err(StatsError::LengthMismatch {
+            expected: data.len(),
+            found: names.len(),
+        }
+        .into());
+    }
+    let mut writer = Writer::from_path(path)?;
+    writer.write_record(["artist", "actual_total", "predicted_total", "residual"])?;
+
+    for ((x, y), name) in data.iter().zip(names) {
+        let predicted = predict(slope, intercept, *x);
+        writer.write_record([
+            name.to_string(),
+            y.to_string(),
+            predicted.to_string(),
+            (y - predicted).to_string(),
+        ])?;
+    }
+
+    writer.flush()?;
+    info!("Predictions saved to {}", path);
+    Ok(())
+}
+
+/// Outcome of analyzing one dataset: records analyzed, malformed rows skipped, and the R² of
+/// each fitted relationship by key.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RunSummary {
+    pub records: usize,
+    pub skipped: usize,
+    pub r_squared: Vec<(&'static str, f64)>,
+}
+
+/// Writes one row per file of a batch run: its status, record counts, and the R² of each
+/// relationship in `keys`. Files whose run stopped before fitting anything, such as those
+/// without usable records, get the status `no_results` and blank numbers, and failed files
+/// carry their error message.
+pub fn write_batch_summary(
+    path: &str,
+    keys: &[&str],
+    rows: &[(String, Result<Option<RunSummary>, String>)],
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = Writer::from_path(path)?;
+    let mut header = vec!["file".to_string(), "status".to_string()];
+    header.extend(["records", "skipped"].map(String::from));
+    header.extend(keys.iter().map(|key| format!("{}_r2", key)));
+    header.push("error".to_string());
+    writer.write_record(&header)?;
+
+    for (file, outcome) in rows {
+        let mut record = vec![file.clone()];
+        match outcome {
+            Ok(summary) => {
+                let status = if summary.is_some() {
+                    "ok"
+                } else {
+                    "no_results"
+                };
+                record.push(status.to_string());
+                let summary = summary.as_ref();
+                record.push(summary.map_or_else(String::new, |s| s.records.to_string()));
+                record.push(summary.map_or_else(String::new, |s| s.skipped.to_string()));
+                for key in keys {
+                    let r_squared = summary
+                        .and_then(|s| s.r_squared.iter().find(|(k, _)| k == key))
+                        .map_or_else(String::new, |(_, r2)| r2.to_string());
+                    record.push(r_squared);
+                }
+                record.push(String::new());
+            }
+            Err(e) => {
+                record.push("failed".to_string());
+                record.extend(std::iter::repeat_n(String::new(), keys.len() + 2));
+                record.push(e.clone());
+            }
+        }
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    info!("Batch summary saved to {}", path);
+    Ok(())
+}
+
+/// Top-level layout of the JSON export.
+#[derive(Serialize)]
+pub struct JsonReport<'a> {
+    pub records: &'a [ArtistData],
+    pub regressions: &'a [RegressionResult],
+}
+
+/// Writes the parsed records and regression results as a JSON object with
+/// `records` and `regressions` arrays, overwriting any existing file at `path`.
+pub fn write_json(
+    path: &str,
+    data: &[ArtistData],
+    results: &[RegressionResult],
+) -> Result<(), Box<dyn Error>> {
+    let report = JsonReport {
+        records: data,
+        regressions: results,
+    };
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &report)?;
+    writer.flush()?;
+    info!("JSON export saved to {}", path);
+    Ok(())
+}
+
+/// A predictor/response pair analyzed with its own regression and plots.
+pub struct Relationship {
+    /// Short name used to select the relationship on the command line.
+    pub key: &'static str,
+    /// Title used in printed output and plot captions.
+    pub name: &'static str,
+    /// Extracts the (x, y) point of an artist.
+    pub extract: fn(&ArtistData) -> (f64, f64),
+    /// Output file name without extension.
+    pub file_stem: &'static str,
+    /// Axis descriptions of the predictor and response.
+    pub x_label: &'static str,
+    pub y_label: &'static str,
+}
+
+/// Returns the relationships analyzed on every run.
+pub fn default_relationships() -> Vec<Relationship> {
+    vec![
+        Relationship {
+            key: "solo",
+            name: "Total Streams vs Solo Streams",
+            extract: |d| (d.solo_streams, d.total_streams),
+            file_stem: "total_streams_vs_solo_streams",
+            x_label: "Solo Streams",
+            y_label: "Total Streams",
+        },
+        Relationship {
+            key: "feature",
+            name: "Total Streams vs Featured Streams",
+            extract: |d| (d.feature_streams, d.total_streams),
+            file_stem: "total_streams_vs_featured_streams",
+            x_label: "Featured Streams",
+            y_label: "Total Streams",
+        },
+        Relationship {
+            key: "lead",
+            name: "Total Streams vs Lead Streams",
+            extract: |d| (d.lead_streams, d.total_streams),
+            file_stem: "total_streams_vs_lead_streams",
+            x_label: "Lead Streams",
+            y_label: "Total Streams",
+        },
+    ]
+}
+
+/// Fills a plot caption template, replacing `{x}` and `{y}` with the axis descriptions and
+/// `{n}` with the number of points.
+pub fn expand_title(template: &str, x_label: &str, y_label: &str, n: usize) -> String {
+    template
+        .replace("{x}", x_label)
+        .replace("{y}", y_label)
+        .replace("{n}", &n.to_string())
+}
+
+/// Returns the relationships whose keys are listed in `keys`, in their default order, or
+/// all of them when `keys` is empty. Unknown keys are an error listing the valid choices.
+pub fn select_relationships(keys: &[String]) -> Result<Vec<Relationship>, String> {
+    let relationships = default_relationships();
+    if let Some(unknown) = keys
+        .iter()
+        .find(|key| !relationships.iter().any(|r| r.key == key.as_str()))
+    {
+        let valid: Vec<&str> = relationships.iter().map(|r| r.key).collect();
+        return Err(format!(
+            "unknown relationship '{}'; valid choices are: {}",
+            unknown,
+            valid.join(", ")
+        ));
+    }
+    Ok(relationships
+        .into_iter()
+        .filter(|r| keys.is_empty() || keys.iter().any(|key| key == r.key))
+        .collect())
+}
+
+///tests for the program as part of the rubric
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_artist_data() {
+        let test_csv = "Name,Total Streams,Solo Streams,Lead Streams,Feature Streams\n\
+                        Artist1,1000,500,300,200\n\
+                        Artist2,2000,800,600,400\n";
+        let rdr = csv::Reader::from_reader(test_csv.as_bytes());
+        let columns = ColumnConfig {
+            name: Column::Name("Name".to_string()),
+            total: Column::Name("Total Streams".to_string()),
+            solo: Column::Name("Solo Streams".to_string()),
+            feature: Column::Name("Feature Streams".to_string()),
+            lead: Column::Name("Lead Streams".to_string()),
+            group: None,
+            comma: CommaStyle::Thousands,
+            negatives: NegativePolicy::Keep,
+            missing: MissingPolicy::Skip,
+        };
+        let data = parse_artist_records(rdr, &columns, false, None)
+            .unwrap()
+            .records;
+        assert_eq!(data.len(), 2);
+        assert_eq!(
+            data[0],
+            ArtistData {
+                name: "Artist1".to_string(),
+                total_streams: 1000.0,
+                solo_streams: 500.0,
+                feature_streams: 200.0,
+                lead_streams: 300.0,
+                group: None,
+                source: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_quoted_names_with_bom() {
+        let test_csv = "\u{feff}Artist,Streams,Daily,As lead,Solo,As feature\n\
+                        \"Tyler, The Creator\",\"9,638.0\",5.1,\"6,197.3\",\"4,218.4\",\"3,440.7\"\n";
+        let rdr = csv_reader_builder().from_reader(test_csv.as_bytes());
+        let columns = ColumnConfig {
+            name: Column::Name("Artist".to_string()),
+            ..ColumnConfig::default()
+        };
+        let parsed = parse_artist_records(rdr, &columns, true, None).unwrap();
+        assert_eq!(parsed.skipped, 0);
+        assert_eq!(
+            parsed.records,
+            vec![ArtistData {
+                name: "Tyler, The Creator".to_string(),
+                total_streams: 9638.0,
+                solo_streams: 6197.3,
+                feature_streams: 3440.7,
+                lead_streams: 4218.4,
+                group: None,
+                source: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_from_cursor() {
+        let input = io::Cursor::new(
+            "Artist,Streams,Daily,As lead,Solo,As feature\n\
+             Drake,\"85,041.3\",50.775,\"57,252.6\",\"32,681.6\",\"27,788.7\"\n"
+                .as_bytes(),
+        );
+        let rdr = csv_reader_builder().from_reader(input);
+        let parsed = parse_artist_records(rdr, &ColumnConfig::default(), true, None).unwrap();
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.records[0].name, "Drake");
+        assert_eq!(parsed.records[0].total_streams, 85041.3);
+        assert_eq!(parsed.records[0].solo_streams, 57252.6);
+    }
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter(b"Artist,Streams,Daily"), b',');
+        assert_eq!(detect_delimiter(b"Artist;Streams;Daily"), b';');
+        assert_eq!(detect_delimiter(b"Artist\tStreams\tDaily"), b'\t');
+        assert_eq!(detect_delimiter(b"\"A,B,C\";\"1,5\";2"), b';');
+        assert_eq!(detect_delimiter(b"Artist"), b',');
+
+        let path = std::env::temp_dir().join("finalproject_test_semicolon.csv");
+        fs::write(
+            &path,
+            "Artist;Streams;Daily;As lead;Solo;As feature\nDrake;85041.3;50.775;57252.6;32681.6;27788.7\n",
+        )
+        .unwrap();
+        let source = CsvSource {
+            path: path.to_string_lossy().into_owned(),
+            delimiter: None,
+            columns: ColumnConfig::default(),
+            skip_malformed: true,
+            progress_every: None,
+        };
+        let parsed = source.records().unwrap();
+        assert_eq!(parsed.records[0].total_streams, 85041.3);
+    }
+
+    #[test]
+    fn test_open_gzip_input() {
+        assert!(is_gzip_path("archive/artists.csv.GZ"));
+        assert!(!is_gzip_path("artists.csv"));
+
+        let path = std::env::temp_dir().join("finalproject_test_artists.csv.gz");
+        let path = path.to_string_lossy();
+        let csv = "Artist,Streams,Daily,As lead,Solo,As feature\n\
+                   Drake,\"85,041.3\",50.775,\"57,252.6\",\"32,681.6\",\"27,788.7\"\n";
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut encoder, csv.as_bytes()).unwrap();
+        fs::write(path.as_ref(), encoder.finish().unwrap()).unwrap();
+
+        let parsed = parse_artist_data(&path, Some(b','), &ColumnConfig::default(), true, None);
+        if cfg!(feature = "gzip") {
+            let records = parsed.unwrap().records;
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].total_streams, 85041.3);
+        } else {
+            assert!(parsed.unwrap_err().to_string().contains("gzip"));
+        }
+    }
+
+    #[test]
+    fn test_parse_artist_files() {
+        let dir = std::env::temp_dir();
+        let header = "Artist,Streams,Daily,As lead,Solo,As feature\n";
+        let first = dir.join("finalproject_test_2021.csv");
+        let second = dir.join("finalproject_test_2022.csv");
+        let other = dir.join("finalproject_test_other_layout.csv");
+        fs::write(&first, format!("{header}Drake,10,1,4,5,3\nSZA,8,1,3,4,2\n")).unwrap();
+        fs::write(&second, format!("{header}Drake,12,1,5,6,4\n")).unwrap();
+        fs::write(&other, "Artist,Streams\nDrake,1\n").unwrap();
+        let paths = |files: &[&std::path::PathBuf]| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let inputs = paths(&[&first, &second]);
+        let files =
+            parse_artist_files(&inputs, None, &ColumnConfig::default(), true, None).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].1.records.len(), 2);
+        assert_eq!(files[1].1.records.len(), 1);
+        assert_eq!(
+            files[1].1.records[0].source.as_deref(),
+            Some(inputs[1].as_str())
+        );
+        assert_eq!(files[1].1.records[0].total_streams, 12.0);
+
+        let inputs = paths(&[&first, &other]);
+        let error = parse_artist_files(&inputs, None, &ColumnConfig::default(), true, None)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(&inputs[1]), "{error}");
+    }
+
+    #[test]
+    fn test_csv_source_with_tab_delimiter() {
+        let path = std::env::temp_dir().join("finalproject_test_source.tsv");
+        fs::write(
+            &path,
+            "Artist\tStreams\tDaily\tAs lead\tSolo\tAs feature\n\
+             Drake\t85,041.3\t50.775\t57,252.6\t32,681.6\t27,788.7\n",
+        )
+        .unwrap();
+        let source = CsvSource {
+            path: path.to_string_lossy().into_owned(),
+            delimiter: Some(b'\t'),
+            columns: ColumnConfig::default(),
+            skip_malformed: true,
+            progress_every: None,
+        };
+
+        let parsed = source.records().unwrap();
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.records[0].total_streams, 85041.3);
+        assert_eq!(parsed.records[0].feature_streams, 27788.7);
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_columns() {
+        let rdr =
+            csv::Reader::from_reader("Artist,Streams,Daily\nDrake,85041.3,50.775\n".as_bytes());
+        let err = parse_artist_records(rdr, &ColumnConfig::default(), true, None)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "expected at least 6 columns, found 3");
+    }
+
+    #[test]
+    fn test_column_resolve() {
+        let headers = StringRecord::from(vec!["Artist", "Streams"]);
+        assert_eq!(Column::Index(4).resolve(&headers).unwrap(), 4);
+        assert_eq!(
+            Column::Name("Streams".to_string())
+                .resolve(&headers)
+                .unwrap(),
+            1
+        );
+        let error = Column::Name("Daily".to_string())
+            .resolve(&headers)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "column 'Daily' not found in CSV header; available columns are: Artist, Streams"
+        );
+    }
+
+    #[test]
+    fn test_log_enabled() {
+        assert!(LogLevel::Quiet < LogLevel::Normal && LogLevel::Normal < LogLevel::Verbose);
+        assert_eq!(LogLevel::default(), LogLevel::Normal);
+        assert!(log_enabled(LogLevel::Quiet));
+        assert!(log_enabled(LogLevel::Normal));
+        assert!(!log_enabled(LogLevel::Verbose));
+    }
+
+    #[test]
+    fn test_parse_stream_value() {
+        assert_eq!(
+            parse_stream_value(Some("57,252.6"), CommaStyle::Thousands),
+            Some(57252.6)
+        );
+        assert_eq!(parse_stream_value(Some("n/a"), CommaStyle::Thousands), None);
+        assert_eq!(
+            parse_stream_value(Some("1,234,567"), CommaStyle::Thousands),
+            Some(1234567.0)
+        );
+        assert_eq!(parse_stream_value(Some("1,5"), CommaStyle::Thousands), None);
+        assert_eq!(
+            parse_stream_value(Some("12,34.0"), CommaStyle::Thousands),
+            None
+        );
+        assert_eq!(parse_stream_value(None, CommaStyle::Thousands), None);
+    }
+
+    #[test]
+    fn test_missing_policy() {
+        let test_csv = "Artist,Streams,Daily,As lead,Solo,As feature\n\
+                        A,10,1,4,6,\n\
+                        B,,1,2,3,8\n\
+                        C,20,1,6,9,2\n\
+                        D,x,1,1,1,1\n";
+        let parse = |policy: &str| {
+            let columns = ColumnConfig {
+                missing: policy.parse().unwrap(),
+                ..ColumnConfig::default()
+            };
+            let rdr = csv_reader_builder().from_reader(test_csv.as_bytes());
+            parse_artist_records(rdr, &columns, true, None).unwrap()
+        };
+
+        let skipped = parse("skip");
+        assert_eq!(skipped.missing, [1, 0, 1, 0]);
+        assert_eq!((skipped.records.len(), skipped.skipped), (1, 3));
+
+        let imputed = parse("impute");
+        assert_eq!(imputed.missing, [1, 0, 1, 0]);
+        assert_eq!((imputed.records.len(), imputed.skipped), (3, 1));
+        assert_eq!(imputed.records[0].feature_streams, 5.0);
+        assert_eq!(imputed.records[1].total_streams, 15.0);
+        assert!(imputed.records.iter().all(|d| !d.total_streams.is_nan()));
+
+        assert!("mean".parse::<MissingPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_negative_policy() {
+        let test_csv = "Artist,Streams,Daily,As lead,Solo,As feature\n\
+                        A,10,1,4,6,4\n\
+                        B,-5,1,2,3,-8\n\
+                        C,7,1,3,-1,4\n";
+        let parse = |policy: &str| {
+            let columns = ColumnConfig {
+                negatives: policy.parse().unwrap(),
+                ..ColumnConfig::default()
+            };
+            let rdr = csv_reader_builder().from_reader(test_csv.as_bytes());
+            parse_artist_records(rdr, &columns, true, None).unwrap()
+        };
+
+        let kept = parse("keep");
+        assert_eq!((kept.records.len(), kept.negative_rows), (3, 2));
+        assert_eq!(kept.records[1].total_streams, -5.0);
+
+        let rejected = parse("reject");
+        assert_eq!((rejected.records.len(), rejected.negative_rows), (1, 2));
+        assert_eq!(rejected.skipped, 0);
+
+        let clamped = parse("clamp");
+        assert_eq!(clamped.negative_rows, 2);
+        assert_eq!(
+            (
+                clamped.records[1].total_streams,
+                clamped.records[1].feature_streams
+            ),
+            (0.0, 0.0)
+        );
+        assert_eq!(clamped.records[2].lead_streams, 0.0);
+        assert!(!clamped.records.iter().any(has_negative_streams));
+
+        assert!("drop".parse::<NegativePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_parse_stream_value_with_decimal_comma() {
+        let decimal = CommaStyle::Decimal;
+        assert_eq!(parse_stream_value(Some("1,5"), decimal), Some(1.5));
+        assert_eq!(
+            parse_stream_value(Some(" 57252,6 "), decimal),
+            Some(57252.6)
+        );
+        assert_eq!(parse_stream_value(Some("1234"), decimal), Some(1234.0));
+        assert_eq!(parse_stream_value(Some("57,252.6"), decimal), None);
+        assert_eq!(parse_stream_value(Some("1.234,5"), decimal), None);
+        assert_eq!(parse_stream_value(Some("1,2,3"), decimal), None);
+        assert_eq!("comma-as-decimal".parse(), Ok(CommaStyle::Decimal));
+        assert!("comma".parse::<CommaStyle>().is_err());
+
+        let test_csv = "Artist;Streams;Daily;As lead;Solo;As feature\n\
+                        Drake;85041,3;50,775;57252,6;32681,6;27788,7\n";
+        let rdr = csv_reader_builder()
+            .delimiter(b';')
+            .from_reader(test_csv.as_bytes());
+        let columns = ColumnConfig {
+            comma: decimal,
+            ..ColumnConfig::default()
+        };
+        let parsed = parse_artist_records(rdr, &columns, true, None).unwrap();
+        assert_eq!(parsed.records[0].total_streams, 85041.3);
+        assert_eq!(parsed.records[0].feature_streams, 27788.7);
+    }
+
+    #[test]
+    fn test_prediction_errors() {
+        // Against y = x the residuals are 1, -1, 2 and -2.
+        let data = [(0.0, 1.0), (1.0, 0.0), (2.0, 4.0), (3.0, 1.0)];
+        assert_eq!(mean_absolute_error(&data, 1.0, 0.0), Ok(1.5));
+        assert!((root_mean_squared_error(&data, 1.0, 0.0).unwrap() - 2.5f64.sqrt()).abs() < 1e-12);
+
+        let exact = [(1.0, 3.0), (2.0, 5.0)];
+        assert_eq!(mean_absolute_error(&exact, 2.0, 1.0), Ok(0.0));
+        assert_eq!(root_mean_squared_error(&exact, 2.0, 1.0), Ok(0.0));
+        assert_eq!(
+            mean_absolute_error(&[], 2.0, 1.0),
+            Err(StatsError::EmptyInput)
+        );
+        assert_eq!(
+            root_mean_squared_error(&[], 2.0, 1.0),
+            Err(StatsError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_cooks_distance() {
+        let data = [(1.0, 1.0), (2.0, 3.0), (3.0, 2.0), (4.0, 5.0), (10.0, 4.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        let distances = cooks_distance(&data, slope, intercept).unwrap();
+        assert_eq!(distances.len(), 5);
+        // The far-right point has by far the most leverage and is poorly fitted.
+        let most = distances
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        assert_eq!(most.0, 4);
+        assert!(distances.iter().all(|d| *d >= 0.0));
+
+        let exact = [(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert_eq!(cooks_distance(&exact, 2.0, 0.0), Ok(vec![0.0; 3]));
+        assert_eq!(
+            cooks_distance(&exact[..2], 2.0, 0.0),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+        let constant_x = [(1.0, 2.0), (1.0, 4.0), (1.0, 6.0)];
+        assert_eq!(
+            cooks_distance(&constant_x, 0.0, 4.0),
+            Err(StatsError::ZeroVariance)
+        );
+    }
+
+    #[test]
+    fn test_theil_sen() {
+        // y = 2x + 1 with one wild point that drags least squares but not Theil-Sen.
+        let mut data: Vec<(f64, f64)> = (0..10).map(|x| (x as f64, 2.0 * x as f64 + 1.0)).collect();
+        data.push((10.0, 500.0));
+        assert_eq!(theil_sen(&data, THEIL_SEN_PAIRS), Ok((2.0, 1.0)));
+        assert!(calculate_regression(&data).unwrap().0 > 10.0);
+
+        let (slope, intercept) = theil_sen(&data, 7).unwrap();
+        assert_eq!((slope, intercept), (2.0, 1.0));
+
+        assert_eq!(
+            theil_sen(&[(1.0, 2.0), (1.0, 3.0)], 10),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(theil_sen(&[], 10), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_log_transform_y() {
+        let data = [
+            (0.0, 1.0),
+            (1.0, std::f64::consts::E),
+            (2.0, 0.0),
+            (3.0, -5.0),
+        ];
+        let (transformed, dropped) = log_transform_y(&data);
+        assert_eq!(dropped, 2);
+        assert_eq!(transformed, vec![(0.0, 0.0), (1.0, 1.0)]);
+
+        let growth: Vec<(f64, f64)> = (0..5)
+            .map(|x| (x as f64, 3.0 * (0.5 * x as f64).exp()))
+            .collect();
+        let (slope, intercept) = calculate_regression(&log_transform_y(&growth).0).unwrap();
+        assert!((slope - 0.5).abs() < 1e-9);
+        assert!((intercept.exp() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_elasticity() {
+        // y = 3 x^1.5, so a 1% change in x is a 1.5% change in y
+        let mut data: Vec<(f64, f64)> = (1..6)
+            .map(|x| (x as f64, 3.0 * (x as f64).powf(1.5)))
+            .collect();
+        data.push((0.0, 4.0));
+        data.push((2.0, -1.0));
+        let (transformed, dropped) = log_transform_xy(&data);
+        assert_eq!((transformed.len(), dropped), (5, 2));
+        assert_eq!(transformed[0], (0.0, 3.0f64.ln()));
+
+        let (value, dropped) = elasticity(&data).unwrap();
+        assert!((value - 1.5).abs() < 1e-9);
+        assert_eq!(dropped, 2);
+        assert_eq!(elasticity(&[(0.0, 1.0)]), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_log_scale_smoothed_line() {
+        let path = std::env::temp_dir().join("finalproject_test_log_smoothed.svg");
+        let path = path.to_string_lossy();
+        let data = vec![(0.0, 5.0), (1.0, 2.0), (2.0, 4.0), (4.0, 8.0), (8.0, 16.0)];
+        let fit = RegressionResult {
+            name: "Test".to_string(),
+            slope: 2.0,
+            standardized_slope: None,
+            intercept: 0.0,
+            r_squared: 1.0,
+            correlation: 1.0,
+            f_statistic: None,
+            p_value: None,
+        };
+        let options = PlotOptions {
+            log_scale: true,
+            smoothed: Some(vec![
+                (0.0, 5.0),
+                (1.0, 3.5),
+                (2.0, 3.0),
+                (4.0, 6.0),
+                (8.0, 12.0),
+            ]),
+            ..PlotOptions::default()
+        };
+        visualize_relationship(
+            &data,
+            &fit,
+            &options,
+            &ChartStyle::default(),
+            "X",
+            "Y",
+            &path,
+        )
+        .unwrap();
+        // The moving average is the dark green polyline with more than the two legend vertices.
+        let contents = fs::read_to_string(path.as_ref()).unwrap();
+        let vertices: Vec<usize> = contents
+            .lines()
+            .filter(|line| line.starts_with("<polyline") && line.contains(r##"stroke="#008000""##))
+            .map(|line| {
+                line.split("points=\"")
+                    .nth(1)
+                    .unwrap()
+                    .split_whitespace()
+                    .count()
+                    - 1
+            })
+            .filter(|count| *count > 2)
+            .collect();
+        assert_eq!(vertices, vec![4]);
+    }
+
+    #[test]
+    fn test_is_font_error() {
+        let text_error: Box<dyn Error> = "no variance".into();
+        assert!(!is_font_error::<BitMapBackend>(text_error.as_ref()));
+        let layout: Box<dyn Error> = Box::new(
+            DrawingAreaErrorKind::<<BitMapBackend as DrawingBackend>::ErrorType>::LayoutError,
+        );
+        assert!(!is_font_error::<BitMapBackend>(layout.as_ref()));
+
+        let data = [(0.0, 1.0), (1.0, 2.0), (2.0, 4.0), (4.0, 8.0)];
+        let fit = RegressionResult {
+            name: "Test".to_string(),
+            slope: 2.0,
+            standardized_slope: None,
+            intercept: 0.0,
+            r_squared: 1.0,
+            correlation: 1.0,
+            f_statistic: None,
+            p_value: None,
+        };
+        let style = ChartStyle {
+            point_radius: 7,
+            ..ChartStyle::default()
+        };
+        for (log_scale, circles) in [(false, 4), (true, 3)] {
+            let options = PlotOptions {
+                log_scale,
+                ..PlotOptions::default()
+            };
+            let mut svg = String::new();
+            {
+                let root = SVGBackend::with_string(&mut svg, (400, 300)).into_drawing_area();
+                draw_plain_relationship(&root, &data, &fit, &options, &style).unwrap();
+            }
+            assert_eq!(svg.matches("<circle").count(), circles);
+            assert_eq!(svg.matches(r#"r="7""#).count(), circles);
+        }
+    }
+
+    #[test]
+    fn test_format_compact() {
+        assert_eq!(format_compact(1_234_567.0), "1.2M");
+        assert_eq!(format_compact(85_041.3), "85.0K");
+        assert_eq!(format_compact(2.5e9), "2.5B");
+        assert_eq!(format_compact(-950.0), "-950.0");
+        assert_eq!(star_vertices(10.0)[0], (0, -10));
+    }
+
+    #[test]
+    fn test_predict() {
+        assert_eq!(predict(2.0, 1.0, 3.0), 7.0);
+        assert_eq!(predict(0.5, -4.0, 0.0), -4.0);
+
+        let data = [(1.0, 3.0), (2.0, 5.0), (3.0, 7.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        assert!((predict(slope, intercept, 10.0) - 21.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_regression() {
+        let data = [(1.0, 2.0), (2.0, 4.0), (3.0, 6.0), (4.0, 100.0)];
+        let (slope, intercept) =
+            calculate_weighted_regression(&data, &[1.0, 1.0, 1.0, 0.0]).unwrap();
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!(intercept.abs() < 1e-9);
+
+        let equal = calculate_weighted_regression(&data, &[2.0; 4]).unwrap();
+        let (ols_slope, ols_intercept) = calculate_regression(&data).unwrap();
+        assert!((equal.0 - ols_slope).abs() < 1e-9);
+        assert!((equal.1 - ols_intercept).abs() < 1e-9);
+
+        assert_eq!(
+            calculate_weighted_regression(&data, &[1.0; 3]),
+            Err(StatsError::LengthMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
+        assert_eq!(
+            calculate_weighted_regression(&data, &[1.0, -1.0, 1.0, 1.0]),
+            Err(StatsError::NegativeWeight(-1.0))
+        );
+        assert_eq!(
+            calculate_weighted_regression(&data, &[0.0; 4]),
+            Err(StatsError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let mut data = vec![
+            artist(10.0, 4.0, 2.0, 6.0),
+            artist(1.0, 1.0, 1.0, 1.0),
+            artist(20.0, 8.0, 4.0, 12.0),
+        ];
+        data[0].name = "Drake".to_string();
+        data[1].name = "Adele".to_string();
+        data[2].name = "Drake".to_string();
+
+        let first = deduplicate(data.clone(), DedupStrategy::KeepFirst);
+        assert_eq!(first, data[..2].to_vec());
+
+        let last = deduplicate(data.clone(), DedupStrategy::KeepLast);
+        assert_eq!(last, vec![data[2].clone(), data[1].clone()]);
+
+        let average = deduplicate(data, DedupStrategy::Average);
+        assert_eq!(average.len(), 2);
+        assert_eq!(average[0].name, "Drake");
+        assert_eq!(
+            (
+                average[0].total_streams,
+                average[0].solo_streams,
+                average[0].feature_streams,
+                average[0].lead_streams
+            ),
+            (15.0, 6.0, 3.0, 9.0)
+        );
+
+        assert_eq!("average".parse(), Ok(DedupStrategy::Average));
+        assert!("mean".parse::<DedupStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_top_n_by_total() {
+        let mut data = vec![
+            artist(5.0, 0.0, 0.0, 0.0),
+            artist(9.0, 0.0, 0.0, 0.0),
+            artist(7.0, 1.0, 0.0, 0.0),
+            artist(7.0, 2.0, 0.0, 0.0),
+        ];
+        data[2].name = "first".to_string();
+        data[3].name = "second".to_string();
+
+        let top = top_n_by_total(data.clone(), 2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].total_streams, 9.0);
+        assert_eq!(top[1].name, "first");
+        assert_eq!(top_n_by_total(data, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_quartiles_by_total() {
+        let data: Vec<ArtistData> = [9.0, 1.0, 5.0, 3.0, 7.0, 2.0, 8.0, 4.0, 6.0, 10.0]
+            .iter()
+            .map(|&total| artist(total, 0.0, 0.0, 0.0))
+            .collect();
+        let quartiles = quartiles_by_total(data);
+        let totals: Vec<Vec<f64>> = quartiles
+            .iter()
+            .map(|q| q.iter().map(|d| d.total_streams).collect())
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                vec![1.0, 2.0, 3.0],
+                vec![4.0, 5.0],
+                vec![6.0, 7.0, 8.0],
+                vec![9.0, 10.0]
+            ]
+        );
+        assert!(quartiles_by_total(Vec::new()).iter().all(Vec::is_empty));
+
+        let path = std::env::temp_dir().join("finalproject_test_quartiles.png");
+        let path = path.to_string_lossy();
+        let series: Vec<CombinedSeries> = (0..4)
+            .map(|i| {
+                (
+                    "Q",
+                    vec![(i as f64 - 2.0, i as f64 - 2.0), (i as f64 + 1.0, 2.0)],
+                    1.0,
+                    0.0,
+                )
+            })
+            .collect();
+        visualize_quartiles(
+            &series,
+            "Quartiles",
+            "x",
+            "y",
+            &ChartStyle::default(),
+            &path,
+        )
+        .unwrap();
+        assert!(fs::metadata(path.as_ref()).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn test_filter_min_streams() {
+        let data = vec![
+            artist(0.5, 0.0, 0.0, 0.0),
+            artist(10.0, 0.0, 0.0, 0.0),
+            artist(3.0, 0.0, 0.0, 0.0),
+            artist(2.0, 0.0, 0.0, 0.0),
+        ];
+        let kept = filter_min_streams(data.clone(), 3.0);
+        assert_eq!(
+            kept.iter().map(|d| d.total_streams).collect::<Vec<_>>(),
+            vec![10.0, 3.0]
+        );
+        assert_eq!(filter_min_streams(data.clone(), 0.0), data);
+        assert!(filter_min_streams(data, 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_histogram_counts() {
+        let (min, width, counts) = histogram_counts(&[0.0, 1.0, 2.5, 9.0, 10.0], 2);
+        assert_eq!((min, width), (0.0, 5.0));
+        assert_eq!(counts, vec![3, 2]);
+
+        let (_, _, counts) = histogram_counts(&[4.0, 4.0], 3);
+        assert_eq!(counts, vec![2, 0, 0]);
+
+        let dir = std::env::temp_dir().join("finalproject_test_histogram_dir.png");
+        fs::create_dir_all(&dir).unwrap();
+        let style = ChartStyle::default();
+        assert!(visualize_histogram(&[1.0, 2.0], 2, "T", &style, &dir.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_box_stats() {
+        let stats = box_stats(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 100.0]).unwrap();
+        assert_eq!((stats.q1, stats.median, stats.q3), (3.0, 5.0, 7.0));
+        assert_eq!((stats.whisker_low, stats.whisker_high), (1.0, 8.0));
+        assert_eq!(stats.outliers, vec![100.0]);
+
+        assert_eq!(quantile_sorted(&[1.0, 2.0, 3.0, 4.0], 0.25), 1.75);
+        assert!(box_stats(&[]).is_none());
+
+        let dir = std::env::temp_dir().join("finalproject_test_boxplot_dir.png");
+        fs::create_dir_all(&dir).unwrap();
+        let series = [("Solo", vec![1.0, 2.0, 3.0])];
+        assert!(
+            visualize_boxplot(&series, &ChartStyle::default(), &dir.to_string_lossy()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_filter_iqr_outliers() {
+        // Q1 = 3, Q3 = 7, so the 1.5 * IQR fences are -3 and 13.
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 100.0, -10.0];
+        let flags = filter_iqr_outliers(&values[..9], 1.5);
+        assert_eq!(flags.iter().filter(|f| **f).count(), 1);
+        assert!(flags[8]);
+
+        let flags = filter_iqr_outliers(&values, 1.5);
+        assert!(flags[8] && flags[9]);
+        assert!(filter_iqr_outliers(&values, 100.0).iter().all(|f| !f));
+        assert!(filter_iqr_outliers(&[], 1.5).is_empty());
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [4.0, 1.0, 3.0, 2.0, 5.0];
+        assert_eq!(percentile(&values, 0.0), Ok(1.0));
+        assert_eq!(percentile(&values, 50.0), Ok(3.0));
+        assert_eq!(percentile(&values, 90.0), Ok(4.6));
+        assert_eq!(percentile(&values, 100.0), Ok(5.0));
+        assert_eq!(values, [4.0, 1.0, 3.0, 2.0, 5.0]);
+        assert_eq!(percentile(&[7.0], 99.0), Ok(7.0));
+    }
+
+    #[test]
+    fn test_percentile_invalid_input() {
+        assert_eq!(
+            percentile(&[1.0, 2.0], 101.0),
+            Err(StatsError::PercentileOutOfRange(101.0))
+        );
+        assert_eq!(percentile(&[], 50.0), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_write_predictions() {
+        let path = std::env::temp_dir().join("finalproject_test_predictions.csv");
+        let path = path.to_string_lossy();
+        let data = [(1.0, 3.0), (2.0, 4.0)];
+
+        write_predictions(&path, &data, 2.0, 0.5, &["Drake", "Tyler, The Creator"]).unwrap();
+        let contents = fs::read_to_string(path.as_ref()).unwrap();
+        assert_eq!(
+            contents,
+            "artist,actual_total,predicted_total,residual\n\
+             Drake,3,2.5,0.5\n\
+             \"Tyler, The Creator\",4,4.5,-0.5\n"
+        );
+
+        assert!(write_predictions(&path, &data, 2.0, 0.5, &["Drake"]).is_err());
+    }
+
+    #[test]
+    fn test_write_report() {
+        let path = std::env::temp_dir().join("finalproject_test_report.csv");
+        let path = path.to_string_lossy();
+        let result = RegressionResult {
+            name: "Solo".to_string(),
+            slope: 2.0,
+            standardized_slope: Some(0.8),
+            intercept: 0.5,
+            r_squared: 0.9,
+            correlation: 0.95,
+            f_statistic: Some(12.5),
+            p_value: None,
+        };
+
+        write_report(&path, &[result.clone(), result]).unwrap();
+        let contents = fs::read_to_string(path.as_ref()).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert_eq!(
+            contents.lines().nth(1),
+            Some("Solo,2,0.8,0.5,0.9,0.95,12.5,")
+        );
+
+        write_report(&path, &[]).unwrap();
+        let contents = fs::read_to_string(path.as_ref()).unwrap();
+        assert_eq!(
+            contents,
+            "relationship,slope,standardized_slope,intercept,r_squared,correlation,f_statistic,p_value\n"
+        );
+    }
+
+    #[test]
+    fn test_spearman_correlation() {
+        assert_eq!(
+            average_ranks(&[10.0, 30.0, 20.0, 20.0]),
+            vec![1.0, 4.0, 2.5, 2.5]
+        );
+
+        // Monotonic but non-linear, so Spearman is exactly 1 while Pearson is not.
+        let data = vec![(1.0, 1.0), (2.0, 4.0), (3.0, 9.0), (4.0, 1000.0)];
+        assert!((spearman_correlation(&data).unwrap() - 1.0).abs() < 1e-9);
+        assert!(pearson_correlation(&data).unwrap() < 0.9);
+        assert_eq!(spearman_correlation(&[]), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_compute_summary() {
+        let odd = compute_summary(&[3.0, 1.0, 2.0]);
+        assert_eq!(odd.count, 3);
+        assert_eq!((odd.mean, odd.median, odd.std_dev), (2.0, 2.0, 1.0));
+        assert_eq!((odd.min, odd.max), (1.0, 3.0));
+
+        let even = compute_summary(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(even.median, 2.5);
+
+        assert!(compute_summary(&[]).mean.is_nan());
+    }
+
+    #[test]
+    fn test_write_json() {
+        let path = std::env::temp_dir().join("finalproject_test_export.json");
+        let path = path.to_string_lossy();
+        let result = RegressionResult {
+            name: "Solo".to_string(),
+            slope: 2.0,
+            standardized_slope: None,
+            intercept: 0.5,
+            r_squared: 0.9,
+            correlation: 0.95,
+            f_statistic: None,
+            p_value: None,
+        };
+
+        write_json(&path, &[artist(3.0, 1.0, 1.0, 1.0)], &[result]).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.as_ref()).unwrap()).unwrap();
+        assert_eq!(json["records"][0]["total_streams"], 3.0);
+        assert_eq!(json["regressions"][0]["name"], "Solo");
+        assert_eq!(json["regressions"][0]["slope"], 2.0);
+
+        #[cfg(target_os = "linux")]
+        assert!(write_json("/dev/full", &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_standardize() {
+        assert_eq!(standardize(&[1.0, 2.0, 3.0]), vec![-1.0, 0.0, 1.0]);
+        assert_eq!(standardize(&[4.0, 4.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_standardized_slope() {
+        let data = vec![(1.0, 10.0), (2.0, 30.0), (3.0, 20.0), (4.0, 40.0)];
+        let (slope, _) = calculate_regression(&data).unwrap();
+        let beta = standardized_slope(&data, slope).unwrap();
+        assert!((beta - pearson_correlation(&data).unwrap()).abs() < 1e-12);
+        // Rescaling y leaves the standardized slope unchanged.
+        let scaled: Vec<(f64, f64)> = data.iter().map(|(x, y)| (*x, y * 1000.0)).collect();
+        let beta_scaled = standardized_slope(&scaled, slope * 1000.0).unwrap();
+        assert!((beta_scaled - beta).abs() < 1e-12);
+
+        assert_eq!(
+            standardized_slope(&[(1.0, 1.0), (1.0, 2.0)], 0.0),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(
+            standardized_slope(&[(1.0, 1.0), (2.0, 1.0)], 0.0),
+            Err(StatsError::ConstantValues)
+        );
+        assert!(standardized_slope(&[(1.0, 1.0)], 1.0).is_err());
+    }
+
+    #[test]
+    fn test_calculate_regression() {
+        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        assert!((slope - 2.0).abs() < 1e-6);
+        assert!((intercept - 0.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_line_points() {
+        assert_eq!(
+            fit_line_points(2.0, -100.0, 60.0, 90.0, false, CURVE_SAMPLES),
+            vec![(60.0, 20.0), (90.0, 80.0)]
+        );
+
+        let curve = fit_line_points(1.0, 0.0, 1.0, 100.0, true, CURVE_SAMPLES);
+        assert_eq!(curve.len(), CURVE_SAMPLES + 1);
+        assert_eq!(curve[0], (1.0, 1.0));
+        let (x, y) = curve[CURVE_SAMPLES];
+        assert!((x - 100.0).abs() < 1e-9 && (y - 100.0).abs() < 1e-9);
+        assert_eq!(fit_line_points(1.0, 0.0, 1.0, 100.0, true, 10).len(), 11);
+
+        let curve = curve_points(0.0, 2.0, 4, |x| x * x);
+        assert_eq!(
+            curve,
+            vec![(0.0, 0.0), (0.5, 0.25), (1.0, 1.0), (1.5, 2.25), (2.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_clip_to_y_range() {
+        assert_eq!(
+            clip_to_y_range(&[(0.0, -10.0), (10.0, 10.0)], 0.0, 5.0),
+            vec![vec![(5.0, 0.0), (7.5, 5.0)]]
+        );
+        assert_eq!(
+            clip_to_y_range(&[(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)], 0.0, 5.0),
+            vec![vec![(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)]]
+        );
+        // A curve leaving through the top and coming back splits into two runs.
+        assert_eq!(
+            clip_to_y_range(&[(0.0, 2.0), (1.0, 6.0), (2.0, 2.0)], 0.0, 4.0),
+            vec![vec![(0.0, 2.0), (0.5, 4.0)], vec![(1.5, 4.0), (2.0, 2.0)]]
+        );
+        assert!(clip_to_y_range(&[(0.0, 8.0), (1.0, 9.0)], 0.0, 4.0).is_empty());
+    }
+
+    #[test]
+    fn test_expand_title() {
+        assert_eq!(
+            expand_title("{x} vs {y} (n = {n}) - My Dataset", "Solo", "Total", 42),
+            "Solo vs Total (n = 42) - My Dataset"
+        );
+        assert_eq!(expand_title("Fixed", "Solo", "Total", 1), "Fixed");
+    }
+
+    #[test]
+    fn test_select_relationships() {
+        let keys = |selected: Vec<Relationship>| -> Vec<&str> {
+            selected.into_iter().map(|r| r.key).collect()
+        };
+        assert_eq!(
+            keys(select_relationships(&[]).unwrap()),
+            ["solo", "feature", "lead"]
+        );
+        let selection = ["lead".to_string(), "solo".to_string()];
+        assert_eq!(
+            keys(select_relationships(&selection).unwrap()),
+            ["solo", "lead"]
+        );
+
+        let err = select_relationships(&["bogus".to_string()])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.contains("solo, feature, lead"), "{err}");
+    }
+
+    #[test]
+    fn test_regress_streaming() {
+        let test_csv = "Artist,Streams,Daily,As lead,Solo,As feature\n\
+                        A,2,0,1,0,0\n\
+                        B,4,0,2,0,0\n\
+                        C,bad,0,5,0,0\n\
+                        D,6,0,3,0,0\n";
+        let rdr = csv::Reader::from_reader(test_csv.as_bytes());
+        let relationships = default_relationships();
+        let fits = regress_streaming(rdr, &ColumnConfig::default(), &relationships).unwrap();
+        assert_eq!(fits.len(), 3);
+        let (slope, intercept) = fits[0].unwrap();
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!(intercept.abs() < 1e-9);
+        assert_eq!(fits[1], None);
+    }
+
+    #[test]
+    fn test_regression_sums_merge() {
+        let data = [(1.0, 2.0), (2.0, 3.5), (4.0, 9.0), (5.0, 9.5)];
+        let sums = |points: &[(f64, f64)]| {
+            let mut sums = RegressionSums::default();
+            for (x, y) in points {
+                sums.push(*x, *y);
+            }
+            sums
+        };
+        let merged = sums(&data[..1]).merge(sums(&data[1..]));
+        assert_eq!(merged.fit(), sums(&data).fit());
+        assert_eq!(merged.fit(), calculate_regression(&data).ok());
+    }
+
+    #[test]
+    fn test_calculate_regression_without_x_variance() {
+        assert_eq!(
+            calculate_regression(&[(1.0, 2.0)]),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(
+            calculate_regression(&[(3.0, 1.0), (3.0, 5.0), (3.0, 9.0)]),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(calculate_regression(&[]), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_online_regression() {
+        let data: Vec<(f64, f64)> = (0..1000)
+            .map(|i| {
+                let x = i as f64;
+                (x, 3.0 * x - 7.0 + ((i * 37) % 11) as f64)
+            })
+            .collect();
+        let mut online = OnlineRegression::default();
+        for (x, y) in &data {
+            online.push(*x, *y);
+        }
+        let (slope, intercept, r) = online.finalize().unwrap();
+        let (batch_slope, batch_intercept) = calculate_regression(&data).unwrap();
+        assert!((slope - batch_slope).abs() < 1e-9);
+        assert!((intercept - batch_intercept).abs() < 1e-6);
+        assert!((r - pearson_correlation(&data).unwrap()).abs() < 1e-9);
+
+        // Shifting x and y by 1e9 leaves the fit unchanged apart from the intercept.
+        let mut shifted = OnlineRegression::default();
+        for (x, y) in &data {
+            shifted.push(x + 1e9, y + 1e9);
+        }
+        let (shifted_slope, _, shifted_r) = shifted.finalize().unwrap();
+        assert!((shifted_slope - slope).abs() < 1e-6);
+        assert!((shifted_r - r).abs() < 1e-9);
+
+        assert_eq!(
+            OnlineRegression::default().finalize(),
+            Err(StatsError::EmptyInput)
+        );
+        let mut vertical = OnlineRegression::default();
+        vertical.push(2.0, 1.0);
+        vertical.push(2.0, 5.0);
+        assert_eq!(vertical.finalize(), Err(StatsError::ZeroVariance));
+    }
+
+    #[test]
+    fn test_calculate_regression_no_intercept() {
+        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert_eq!(calculate_regression_no_intercept(&data), Ok(2.0));
+
+        // sum(xy) = 1 * 3 + 2 * 4 = 11 and sum(x²) = 5, unlike the unconstrained slope of 1
+        let offset = vec![(1.0, 3.0), (2.0, 4.0)];
+        assert_eq!(calculate_regression_no_intercept(&offset), Ok(2.2));
+        assert_eq!(calculate_regression(&offset), Ok((1.0, 2.0)));
+
+        assert_eq!(
+            calculate_regression_no_intercept(&[(0.0, 1.0)]),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(
+            calculate_regression_no_intercept(&[]),
+            Err(StatsError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_calculate_r_squared() {
+        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert!((calculate_r_squared(&data, 2.0, 0.0).unwrap() - 1.0).abs() < 1e-6);
+
+        let flat = vec![(1.0, 5.0), (2.0, 5.0), (3.0, 5.0)];
+        assert_eq!(calculate_r_squared(&flat, 0.0, 5.0), Ok(0.0));
+        assert_eq!(
+            calculate_r_squared(&[], 1.0, 0.0),
+            Err(StatsError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_sample_records() {
+        let data: Vec<ArtistData> = (0..20).map(|i| artist(i as f64, 0.0, 0.0, 0.0)).collect();
+        let totals = |sample: &[ArtistData]| -> Vec<f64> {
+            sample.iter().map(|d| d.total_streams).collect()
+        };
+
+        let sample = sample_records(data.clone(), 5, 42);
+        assert_eq!(sample.len(), 5);
+        assert!(totals(&sample).windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            totals(&sample),
+            totals(&sample_records(data.clone(), 5, 42))
+        );
+        assert_ne!(
+            totals(&sample),
+            totals(&sample_records(data.clone(), 5, 43))
+        );
+
+        assert_eq!(sample_records(data.clone(), 50, 1), data);
+    }
+
+    #[test]
+    fn test_trimmed_mean_and_winsorize() {
+        let values = [100.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, -50.0];
+        assert_eq!(trimmed_mean(&values, 0.1), Ok(4.5));
+        assert_eq!(trimmed_mean(&values, 0.0), Ok(8.6));
+        assert_eq!(
+            winsorize(&values, 0.1),
+            Ok(vec![8.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 1.0])
+        );
+        assert_eq!(trimmed_mean(&[3.0], 0.49), Ok(3.0));
+
+        assert_eq!(
+            trimmed_mean(&values, 0.5),
+            Err(StatsError::InvalidProportion(0.5))
+        );
+        assert_eq!(
+            winsorize(&values, -0.1),
+            Err(StatsError::InvalidProportion(-0.1))
+        );
+        assert_eq!(winsorize(&[], 0.1), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_skewness_and_kurtosis() {
+        let symmetric = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(skewness(&symmetric), Ok(0.0));
+        assert!((excess_kurtosis(&symmetric).unwrap() + 1.3).abs() < 1e-12);
+
+        let right_skewed = [1.0, 1.0, 1.0, 2.0, 10.0];
+        assert!(skewness(&right_skewed).unwrap() > 1.0);
+        assert!(skewness(&right_skewed.map(|v| -v)).unwrap() < -1.0);
+        assert!(excess_kurtosis(&right_skewed).unwrap() > 0.0);
+
+        assert_eq!(skewness(&[]), Err(StatsError::EmptyInput));
+        assert_eq!(
+            excess_kurtosis(&[2.0, 2.0]),
+            Err(StatsError::ConstantValues)
+        );
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(
+            moving_average(&values, 3),
+            Ok(vec![1.5, 2.0, 3.0, 4.0, 4.5])
+        );
+        assert_eq!(
+            moving_average(&values, 2),
+            Ok(vec![1.5, 2.5, 3.5, 4.5, 5.0])
+        );
+        assert_eq!(moving_average(&values, 1), Ok(values.to_vec()));
+        assert_eq!(moving_average(&values, 50), Ok(vec![3.0; 5]));
+        assert_eq!(moving_average(&[], 3), Ok(vec![]));
+        assert_eq!(moving_average(&values, 0), Err(StatsError::ZeroWindow));
+    }
+
+    #[test]
+    fn test_visualize_correlation_heatmap() {
+        assert_eq!(correlation_color(0.0), WHITE);
+        assert_eq!(correlation_color(1.0), CORRELATION_POSITIVE);
+        assert_eq!(correlation_color(-2.0), CORRELATION_NEGATIVE);
+
+        let path = std::env::temp_dir().join("finalproject_test_heatmap.png");
+        let path = path.to_string_lossy();
+        let _ = fs::remove_file(path.as_ref());
+        let mut matrix = [[0.5; 4]; 4];
+        matrix[0][3] = -0.8;
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        visualize_correlation_heatmap(&matrix, &VARIABLE_NAMES, &ChartStyle::default(), &path)
+            .unwrap();
+        assert!(fs::metadata(path.as_ref()).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn test_visualize_slope_comparison() {
+        let path = std::env::temp_dir().join("finalproject_test_slopes.png");
+        let path = path.to_string_lossy();
+        let style = ChartStyle::default();
+        assert!(visualize_slope_comparison(&[], &style, &path).is_err());
+
+        let _ = fs::remove_file(path.as_ref());
+        visualize_slope_comparison(&[("Solo", 1.29), ("Featured", -0.4)], &style, &path).unwrap();
+        assert!(fs::metadata(path.as_ref()).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn test_stats_box() {
+        let stats = StatsBox {
+            n: 2598,
+            r_squared: 0.89415,
+            correlation: 0.94559,
+        };
+        assert_eq!(stats.lines(), ["n = 2598", "R² = 0.8942", "r = 0.9456"]);
+        assert_eq!("bottom-right".parse(), Ok(Corner::BottomRight));
+        assert!("middle".parse::<Corner>().is_err());
+    }
+
+    #[test]
+    fn test_palette() {
+        let mut palette = Palette::default();
+        assert_eq!(palette.colors("feature").point, GREEN);
+        assert_eq!(palette.colors("total"), SeriesColors::default());
+
+        let colors = SeriesColors {
+            point: parse_color("#00ff80").unwrap(),
+            line: parse_color("Orange").unwrap(),
+        };
+        palette.set("solo", colors);
+        palette.set("solo", colors);
+        assert_eq!(palette.colors("solo").point, RGBColor(0, 255, 128));
+        assert_eq!(palette.entries.len(), 3);
+
+        assert!(parse_color("#12345g").is_err());
+        assert!(parse_color("mauve").is_err());
+    }
+
+    #[test]
+    fn test_padded_range() {
+        assert_eq!(padded_range(10.0, 100.0), (0.0, 105.0));
+        assert_eq!(padded_range(-20.0, 80.0), (-25.0, 85.0));
+        assert_eq!(padded_range(0.0, 0.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_chart_bounds() {
+        assert!(chart_bounds(&[]).is_err());
+
+        let bounds = chart_bounds(&[(1.0, 2.0), (f64::NAN, 8.0), (4.0, f64::NAN)]).unwrap();
+        assert_eq!(bounds, (4.0, 8.0));
+
+        assert!(chart_bounds(&[(f64::NAN, f64::NAN)]).is_err());
+    }
+
+    fn artist(total: f64, solo: f64, feature: f64, lead: f64) -> ArtistData {
+        ArtistData {
+            name: String::new(),
+            total_streams: total,
+            solo_streams: solo,
+            feature_streams: feature,
+            lead_streams: lead,
+            group: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by() {
+        let test_csv = "Artist,Streams,Daily,As lead,Solo,As feature,Genre\n\
+                        A,10,1,4,3,6,pop\n\
+                        B,20,1,8,6,12, rap \n\
+                        C,30,1,12,9,18,pop\n\
+                        D,40,1,16,12,24,\n";
+        let columns = ColumnConfig {
+            group: Some(Column::Name("Genre".to_string())),
+            ..ColumnConfig::default()
+        };
+        let parsed = parse_artist_records(
+            csv_reader_builder().from_reader(test_csv.as_bytes()),
+            &columns,
+            true,
+            None,
+        )
+        .unwrap();
+        let groups: Vec<(String, Vec<String>)> = group_by(parsed.records)
+            .into_iter()
+            .map(|(group, members)| (group, members.into_iter().map(|d| d.name).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("pop".to_string(), vec!["A".to_string(), "C".to_string()]),
+                ("rap".to_string(), vec!["B".to_string()]),
+                (ALL_GROUP.to_string(), vec!["D".to_string()]),
+            ]
+        );
+
+        let ungrouped = group_by(vec![artist(1.0, 0.0, 0.0, 0.0), artist(2.0, 0.0, 0.0, 0.0)]);
+        assert_eq!(ungrouped.len(), 1);
+        assert_eq!(ungrouped[0].0, ALL_GROUP);
+        assert_eq!(ungrouped[0].1.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_by_name() {
+        let mut data = vec![artist(1.0, 1.0, 0.0, 1.0); 3];
+        data[0].name = "DJ Snake".to_string();
+        data[1].name = "Drake".to_string();
+        data[2].name = "Major Lazer & dj".to_string();
+
+        let matches = filter_by_name(&data, "DJ");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].name, "DJ Snake");
+        assert!(filter_by_name(&data, "Taylor").is_empty());
+    }
+
+    #[test]
+    fn test_stream_breakdown() {
+        let data = vec![
+            artist(100.0, 50.0, 30.0, 20.0),
+            artist(100.0, 10.0, 10.0, 10.0),
+        ];
+        assert_eq!(
+            stream_breakdown(&data),
+            StreamBreakdown {
+                solo_percent: 30.0,
+                feature_percent: 20.0,
+                lead_percent: 15.0,
+            }
+        );
+
+        let inconsistent = inconsistent_artists(&data, 0.01);
+        assert_eq!(inconsistent, vec![&data[1]]);
+    }
+
+    #[test]
+    fn test_covariance_matrix() {
+        let data = vec![
+            artist(1.0, 2.0, 5.0, 7.0),
+            artist(2.0, 4.0, 5.0, 5.0),
+            artist(3.0, 6.0, 5.0, 3.0),
+        ];
+        let covariance = covariance_matrix(&data);
+        assert_eq!(covariance[0], [1.0, 2.0, 0.0, -2.0]);
+        assert_eq!(covariance[1][1], 4.0);
+        assert_eq!(covariance[3][0], covariance[0][3]);
+
+        let correlation = correlation_matrix(&data);
+        assert_eq!(correlation[0][0], 1.0);
+        assert_eq!(correlation[0][1], 1.0);
+        assert_eq!(correlation[0][3], -1.0);
+        assert_eq!(correlation[2], [0.0; 4]);
+
+        assert_eq!(covariance_matrix(&data[..1]), [[0.0; 4]; 4]);
+    }
+
+    #[test]
+    fn test_multiple_regression() {
+        // total = 10 + 1 * solo + 2 * feature + 3 * lead
+        let data: Vec<ArtistData> = [
+            (1.0, 0.0, 2.0),
+            (0.0, 1.0, 1.0),
+            (2.0, 3.0, 0.0),
+            (4.0, 1.0, 5.0),
+            (3.0, 2.0, 2.0),
+        ]
+        .iter()
+        .map(|&(s, f, l)| artist(10.0 + s + 2.0 * f + 3.0 * l, s, f, l))
+        .collect();
+        let coefficients = multiple_regression(&data).unwrap();
+        for (actual, expected) in coefficients.iter().zip([10.0, 1.0, 2.0, 3.0]) {
+            assert!((actual - expected).abs() < 1e-6);
+        }
+        assert!((multiple_r_squared(&data, &coefficients) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ridge_regression() {
+        let data: Vec<ArtistData> = [
+            (1.0, 0.0, 2.0),
+            (0.0, 1.0, 1.0),
+            (2.0, 3.0, 0.0),
+            (4.0, 1.0, 5.0),
+            (3.0, 2.0, 2.0),
+        ]
+        .iter()
+        .map(|&(s, f, l)| artist(10.0 + s + 2.0 * f + 3.0 * l, s, f, l))
+        .collect();
+        let ols = ridge_regression(&data, 0.0).unwrap();
+        for (actual, expected) in ols.iter().zip(multiple_regression(&data).unwrap()) {
+            assert!((actual - expected).abs() < 1e-6);
+        }
+
+        let shrunk = ridge_regression(&data, 5.0).unwrap();
+        let norm = |c: &[f64]| c[1..].iter().map(|b| b * b).sum::<f64>();
+        assert!(norm(&shrunk) < norm(&ols));
+        assert!(multiple_r_squared(&data, &shrunk) < 1.0);
+
+        // Lead streams are twice the feature streams, which OLS cannot separate.
+        let collinear: Vec<ArtistData> = [(1.0, 0.0), (0.0, 1.0), (2.0, 3.0), (4.0, 1.0)]
+            .iter()
+            .map(|&(s, f)| artist(s + 3.0 * f, s, f, 2.0 * f))
+            .collect();
+        assert_eq!(
+            ridge_regression(&collinear, 0.0),
+            Err(StatsError::SingularSystem)
+        );
+        let fit = ridge_regression(&collinear, 1.0).unwrap();
+        assert!((fit[2] - 2.0 * fit[3]).abs() < 1e-9);
+
+        let constant: Vec<ArtistData> = (0..5)
+            .map(|i| artist(i as f64, i as f64, 1.0, 0.0))
+            .collect();
+        assert_eq!(
+            ridge_regression(&constant, 1.0),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(ridge_regression(&[], 1.0), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_multiple_regression_collinear_predictors() {
+        // Lead streams are almost exactly twice the feature streams.
+        let data: Vec<ArtistData> = [
+            (1.0, 0.0, 0.0),
+            (0.0, 1.0, 2.0),
+            (2.0, 3.0, 6.0),
+            (4.0, 1.0, 2.001),
+            (3.0, 2.0, 4.0),
+        ]
+        .iter()
+        .map(|&(s, f, l)| artist(s + f + l, s, f, l))
+        .collect();
+        match multiple_regression(&data) {
+            Err(StatsError::Collinear {
+                first,
+                second,
+                correlation,
+            }) => {
+                assert_eq!((first, second), ("Featured Streams", "Lead Streams"));
+                assert!(correlation > 0.99);
+            }
+            other => panic!("expected a collinearity error, got {:?}", other),
+        }
+        assert_eq!(check_collinearity(&data, 1.0), Ok(()));
+
+        let constant: Vec<ArtistData> = (0..5)
+            .map(|i| artist(i as f64, i as f64, 1.0, 0.0))
+            .collect();
+        assert_eq!(
+            multiple_regression(&constant),
+            Err(StatsError::SingularSystem)
+        );
+    }
+
+    #[test]
+    fn test_find_outliers() {
+        let mut values = vec![10.0; 20];
+        values.push(100.0);
+        assert_eq!(find_outliers(&values, 3.0), vec![20]);
+        assert_eq!(find_outliers(&[1.0, 2.0, 3.0], 2.0), Vec::<usize>::new());
+        assert_eq!(find_outliers(&[5.0, 5.0], 1.0), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_visualize_relationship_svg() {
+        let path = std::env::temp_dir().join("finalproject_test_plot.svg");
+        let path = path.to_string_lossy();
+        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        let fit = RegressionResult {
+            name: "Test".to_string(),
+            slope: 2.0,
+            standardized_slope: Some(1.0),
+            intercept: 0.0,
+            r_squared: 1.0,
+            correlation: 1.0,
+            f_statistic: None,
+            p_value: None,
+        };
+
+        visualize_relationship(
+            &data,
+            &fit,
+            &PlotOptions::default(),
+            &ChartStyle::default(),
+            "X",
+            "Y",
+            &path,
+        )
+        .unwrap();
+        let contents = fs::read_to_string(path.as_ref()).unwrap();
+        assert!(contents.starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_format_polynomial() {
+        assert_eq!(
+            format_polynomial(&[3.0, -2.0, 0.5]),
+            "y = 3.00 - 2.0000e0x + 5.0000e-1x^2"
+        );
+        assert_eq!(format_polynomial(&[-1.0, 4.0]), "y = -1.00 + 4.0000e0x");
+    }
+
+    #[test]
+    fn test_calculate_polynomial_regression() {
+        let data: Vec<(f64, f64)> = (0..10)
+            .map(|x| {
+                let x = x as f64 * 100.0;
+                (x, 3.0 + 2.0 * x + 0.5 * x * x)
+            })
+            .collect();
+        let coefficients = calculate_polynomial_regression(&data, 2).unwrap();
+        for (actual, expected) in coefficients.iter().zip([3.0, 2.0, 0.5]) {
+            assert!((actual - expected).abs() < 1e-6);
+        }
+        assert_eq!(evaluate_polynomial(&coefficients, 0.0), coefficients[0]);
+        assert_eq!(
+            calculate_polynomial_regression(&[(1.0, 1.0), (2.0, 2.0)], 2),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            calculate_polynomial_regression(&[(0.0, 1.0), (0.0, 2.0)], 1),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(
+            calculate_polynomial_regression(&[], 2),
+            Err(StatsError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_adjusted_r_squared() {
+        assert!((adjusted_r_squared(0.8, 11, 1) - 0.7777777777777778).abs() < 1e-12);
+        assert!((adjusted_r_squared(0.8, 11, 3) - (1.0 - 0.2 * 10.0 / 7.0)).abs() < 1e-12);
+        assert_eq!(adjusted_r_squared(1.0, 5, 3), 1.0);
+        assert!(adjusted_r_squared(0.9, 4, 3).is_nan());
+        assert!(adjusted_r_squared(0.9, 0, 0).is_nan());
+    }
+
+    #[test]
+    fn test_compare_models() {
+        let data: Vec<(f64, f64)> = (0..20)
+            .map(|i| {
+                let x = i as f64;
+                let noise = if i % 2 == 0 { 0.5 } else { -0.5 };
+                (x, 1.0 + 0.3 * x * x + noise)
+            })
+            .collect();
+        let fits = compare_models(&data).unwrap();
+        let names: Vec<&str> = fits.iter().map(|fit| fit.name).collect();
+        assert_eq!(names[0], "quadratic");
+        assert_eq!(fits.len(), 3);
+        assert!(fits.windows(2).all(|w| w[0].aic <= w[1].aic));
+        assert!(fits[0].r_squared > 0.99);
+
+        let linear = fits.iter().find(|fit| fit.name == "linear").unwrap();
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        let r_squared = calculate_r_squared(&data, slope, intercept).unwrap();
+        assert!((linear.r_squared - r_squared).abs() < 1e-12);
+
+        let no_positive = [(0.0, -1.0), (1.0, -2.0), (2.0, -4.0)];
+        let names: Vec<&str> = compare_models(&no_positive)
+            .unwrap()
+            .iter()
+            .map(|fit| fit.name)
+            .collect();
+        assert!(!names.contains(&"exponential (log-y)"));
+        assert!(compare_models(&[]).is_err());
+    }
+
+    #[test]
+    fn test_compute_residuals() {
+        let data = vec![(1.0, 3.0), (2.0, 4.0), (3.0, 5.0)];
+        assert_eq!(compute_residuals(&data, 2.0, 0.0), Ok(vec![1.0, 0.0, -1.0]));
+        assert_eq!(
+            compute_residuals(&[], 2.0, 0.0),
+            Err(StatsError::EmptyInput)
+        );
+
+        let path = std::env::temp_dir().join("finalproject_test_residuals.png");
+        let path = path.to_string_lossy();
+        let _ = fs::remove_file(path.as_ref());
+        let exact = vec![(-2.0, -4.0), (1.0, 2.0), (3.0, 6.0)];
+        let style = ChartStyle::default();
+        visualize_residuals(&exact, &[0.0; 3], "solo", "T", "X", &style, &path).unwrap();
+        assert!(fs::metadata(path.as_ref()).unwrap().len() > 0);
+        assert!(visualize_residuals(&exact, &[0.0; 2], "solo", "T", "X", &style, &path).is_err());
+    }
+
+    #[test]
+    fn test_slope_confidence_interval() {
+        let data = vec![(1.0, 1.0), (2.0, 3.0), (3.0, 2.0), (4.0, 4.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        // SS_res = 1.8 over 2 degrees of freedom, Sxx = 5
+        let se = slope_standard_error(&data, slope, intercept).unwrap();
+        assert!((se - (0.9f64 / 5.0).sqrt()).abs() < 1e-9);
+
+        let (lower, upper) = slope_confidence_interval(&data, slope, intercept).unwrap();
+        assert!((upper - lower - 2.0 * 4.303 * se).abs() < 1e-9);
+        assert_eq!(
+            slope_standard_error(&data[..2], slope, intercept),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+        let constant_x = [(1.0, 2.0), (1.0, 4.0), (1.0, 6.0)];
+        assert_eq!(
+            slope_standard_error(&constant_x, 0.0, 4.0),
+            Err(StatsError::ZeroVariance)
+        );
+    }
+
+    #[test]
+    fn test_t_critical_95() {
+        assert_eq!(t_critical_95(1), 12.706);
+        assert_eq!(t_critical_95(30), 2.042);
+        assert!((t_critical_95(31) - 2.040).abs() < 1e-3);
+        assert!((t_critical_95(120) - 1.980).abs() < 1e-3);
+        assert!((t_critical_95(100_000) - CRITICAL_VALUE_95).abs() < 1e-4);
+        assert!(t_critical_95(0).is_infinite());
+    }
+
+    #[test]
+    fn test_confidence_band() {
+        let data = vec![(1.0, 1.0), (2.0, 3.0), (3.0, 2.0), (4.0, 4.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        let band = confidence_band(&data, slope, intercept, 6).unwrap();
+        assert_eq!(band.len(), 7);
+        assert_eq!((band[0].0, band[6].0), (1.0, 4.0));
+
+        // At the mean of x the half-width is t(2) * s / sqrt(n), with s² = 1.8 / 2.
+        let (x, lower, upper) = band[3];
+        assert_eq!(x, 2.5);
+        let center = predict(slope, intercept, x);
+        assert!((upper - center - 4.303 * (0.9f64 / 4.0).sqrt()).abs() < 1e-9);
+        assert!((center - lower - (upper - center)).abs() < 1e-9);
+        assert!(band[0].2 - band[0].1 > upper - lower);
+
+        assert!(confidence_band(&data[..2], slope, intercept, 7).is_err());
+    }
+
+    #[test]
+    fn test_f_test() {
+        assert!((erfc(0.0) - 1.0).abs() < 1e-6);
+        assert!((erfc(1.0) - 0.157_299_2).abs() < 1e-6);
+        assert!((erfc(-1.0) - 1.842_700_8).abs() < 1e-6);
+
+        let data = vec![(1.0, 1.0), (2.0, 3.0), (3.0, 2.0), (4.0, 4.0)];
+        let (slope, intercept) = calculate_regression(&data).unwrap();
+        // SS_tot = 5 and SS_res = 1.8, so F = 3.2 / (1.8 / 2)
+        let (f, p) = f_test(&data, slope, intercept).unwrap();
+        assert!((f - 3.2 / 0.9).abs() < 1e-9);
+        assert!((p - erfc((f / 2.0).sqrt())).abs() < 1e-12);
+        assert!(p > 0.05 && p < 0.07);
+
+        let exact = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert_eq!(f_test(&exact, 2.0, 0.0), Ok((f64::INFINITY, 0.0)));
+        let constant_y = vec![(1.0, 5.0), (2.0, 5.0), (3.0, 5.0)];
+        assert_eq!(
+            f_test(&constant_y, 0.0, 5.0),
+            Err(StatsError::ConstantValues)
+        );
+        assert_eq!(
+            f_test(&data[..2], slope, intercept),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_compare_slopes() {
+        let a = vec![(1.0, 1.0), (2.0, 3.0), (3.0, 2.0), (4.0, 4.0)];
+        let b: Vec<(f64, f64)> = a.iter().map(|(x, y)| (*x, y + 0.5 * x)).collect();
+        let se = (0.9f64 / 5.0).sqrt();
+
+        let comparison = compare_slopes(&b, &a).unwrap();
+        assert!((comparison.difference - 0.5).abs() < 1e-9);
+        assert!((comparison.standard_error - se * 2f64.sqrt()).abs() < 1e-9);
+        assert!((comparison.z - comparison.difference / comparison.standard_error).abs() < 1e-12);
+        assert!(comparison.p_value > 0.05 && comparison.p_value < 1.0);
+        assert_eq!(compare_slopes(&a, &a).unwrap().p_value, erfc(0.0));
+
+        assert_eq!(
+            compare_slopes(&a, &a[..2]),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_jackknife_slope() {
+        let data: Vec<(f64, f64)> = (0..20)
+            .map(|i| (i as f64, 2.0 * i as f64 + ((i * 7) % 5) as f64))
+            .collect();
+        let (slope, _) = calculate_regression(&data).unwrap();
+        let refits: Vec<f64> = (0..data.len())
+            .map(|i| {
+                let mut rest = data.clone();
+                rest.remove(i);
+                calculate_regression(&rest).unwrap().0
+            })
+            .collect();
+        let n = data.len() as f64;
+        let mean = refits.iter().sum::<f64>() / n;
+        let expected_se =
+            ((n - 1.0) / n * refits.iter().map(|s| (s - mean).powi(2)).sum::<f64>()).sqrt();
+
+        let (se, bias) = jackknife_slope(&data).unwrap();
+        assert!((se - expected_se).abs() < 1e-9);
+        assert!((bias - (n - 1.0) * (mean - slope)).abs() < 1e-9);
+
+        let exact = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0), (4.0, 8.0)];
+        let (se, bias) = jackknife_slope(&exact).unwrap();
+        assert!(se.abs() < 1e-9 && bias.abs() < 1e-9);
+
+        assert_eq!(
+            jackknife_slope(&exact[..2]),
+            Err(StatsError::TooFewPoints {
+                needed: 3,
+                found: 2
+            })
+        );
+        let two_x = vec![(1.0, 1.0), (1.0, 2.0), (2.0, 3.0)];
+        assert_eq!(jackknife_slope(&two_x), Err(StatsError::ZeroVariance));
+        let residue = vec![(1234.567, 1.0), (1234.567, 2.0), (98765.4321, 3.0)];
+        assert_eq!(
+            calculate_regression(&residue[..2]),
+            Err(StatsError::ZeroVariance)
+        );
+        assert_eq!(jackknife_slope(&residue), Err(StatsError::ZeroVariance));
+
+        let offset: Vec<(f64, f64)> = (0..30)
+            .map(|i| (1e8 + i as f64, 3e8 + 0.5 * i as f64 + ((i * 11) % 7) as f64))
+            .collect();
+        let refits: Vec<f64> = (0..offset.len())
+            .map(|i| {
+                let mut rest = offset.clone();
+                rest.remove(i);
+                let mut online = OnlineRegression::default();
+                for (x, y) in &rest {
+                    online.push(*x, *y);
+                }
+                online.finalize().unwrap().0
+            })
+            .collect();
+        let n = offset.len() as f64;
+        let mean = refits.iter().sum::<f64>() / n;
+        let expected_se =
+            ((n - 1.0) / n * refits.iter().map(|s| (s - mean).powi(2)).sum::<f64>()).sqrt();
+        let (se, _) = jackknife_slope(&offset).unwrap();
+        assert!((se - expected_se).abs() < 1e-6 * expected_se);
+    }
+
+    #[test]
+    fn test_bootstrap_slope_ci() {
+        let data: Vec<(f64, f64)> = (0..50)
+            .map(|i| (i as f64, 2.0 * i as f64 + ((i * 7) % 5) as f64))
+            .collect();
+        let (slope, _) = calculate_regression(&data).unwrap();
+        let (lower, upper) = bootstrap_slope_ci(&data, 500, 0.95, 1).unwrap();
+        assert!(lower < slope && slope < upper);
+        assert!(upper - lower < 0.2);
+        assert_eq!(bootstrap_slope_ci(&data, 500, 0.95, 1), Ok((lower, upper)));
+
+        let exact = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        let (lower, upper) = bootstrap_slope_ci(&exact, 100, 0.9, 3).unwrap();
+        assert!((lower - 2.0).abs() < 1e-9 && (upper - 2.0).abs() < 1e-9);
+
+        assert_eq!(
+            bootstrap_slope_ci(&data, 10, 1.0, 1),
+            Err(StatsError::InvalidLevel(1.0))
+        );
+        assert_eq!(
+            bootstrap_slope_ci(&data, 0, 0.95, 1),
+            Err(StatsError::ZeroVariance)
+        );
+    }
+
+    #[test]
+    fn test_hexbin_counts() {
+        assert!(hexbin_counts(&[], 10).is_empty());
+        assert!(hexbin_counts(&[(1.0, 1.0)], 0).is_empty());
+
+        let data = vec![
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.01, 0.0),
+            (10.0, 10.0),
+            (5.0, 5.0),
+        ];
+        let cells = hexbin_counts(&data, 10);
+        assert_eq!(
+            cells.iter().map(|(_, count)| count).sum::<usize>(),
+            data.len()
+        );
+        assert_eq!(cells[0], ((0.0, 0.0), 3));
+        assert_eq!(cells.len(), 3);
+
+        // A point halfway up a row falls into the shifted lattice.
+        let shifted = hexbin_counts(&[(0.0, 0.0), (0.5, 0.8), (1.0, 1.0)], 1);
+        assert!(shifted.iter().any(|((x, y), count)| *x == 0.5
+            && (y - 3f64.sqrt() / 2.0).abs() < 1e-12
+            && *count == 1));
+
+        let dir = std::env::temp_dir().join("finalproject_test_hexbin_dir.png");
+        fs::create_dir_all(&dir).unwrap();
+        let style = ChartStyle::default();
+        let path = dir.to_string_lossy();
+        assert!(visualize_hexbin(&data, 10, "T", "X", "Y", &style, &path).is_err());
+    }
+
+    #[test]
+    fn test_pearson_correlation() {
+        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert!((pearson_correlation(&data).unwrap() - 1.0).abs() < 1e-6);
+
+        let inverse = vec![(1.0, 6.0), (2.0, 4.0), (3.0, 2.0)];
+        assert!((pearson_correlation(&inverse).unwrap() + 1.0).abs() < 1e-6);
+
+        let constant_x = vec![(1.0, 2.0), (1.0, 4.0), (1.0, 6.0)];
+        assert_eq!(pearson_correlation(&constant_x), Ok(0.0));
+        assert_eq!(pearson_correlation(&[]), Err(StatsError::EmptyInput));
+    }
+
+    #[test]
+    fn test_top_outlier_indices() {
+        let data = vec![(1.0, 2.0), (2.0, 10.0), (3.0, 6.0), (4.0, 1.0)];
+        assert_eq!(top_outlier_indices(&data, 2.0, 0.0, 2), vec![3, 1]);
+    }
+}
@@ -1,187 +1,2033 @@
-use csv::Reader;
-use plotters::prelude::*;
+use finalproject::{
+    adjusted_r_squared, bootstrap_slope_ci, calculate_polynomial_regression, calculate_r_squared,
+    calculate_regression, calculate_regression_no_intercept, calculate_weighted_regression,
+    compare_models, compare_slopes, compute_residuals, compute_summary, confidence_band,
+    cooks_distance, correlation_matrix, covariance_matrix, debug, deduplicate, elasticity,
+    excess_kurtosis, expand_title, f_test, filter_by_name, filter_iqr_outliers, filter_min_streams,
+    find_outliers, format_compact, format_polynomial, group_by, inconsistent_artists, info,
+    jackknife_slope, log_enabled, log_transform_y, mean_absolute_error, moving_average,
+    multiple_r_squared, multiple_regression, open_input, parse_artist_files, parse_color,
+    pearson_correlation, percentile, predict, print_matrix, print_summary_table,
+    quartiles_by_total, regress_streaming, ridge_regression, root_mean_squared_error,
+    sample_records, select_relationships, set_log_level, skewness, slope_confidence_interval,
+    spearman_correlation, standardize, standardized_slope, status, stream_breakdown, theil_sen,
+    top_n_by_total, top_outlier_indices, trimmed_mean, visualize_boxplot, visualize_combined,
+    visualize_correlation_heatmap, visualize_hexbin, visualize_histogram, visualize_quartiles,
+    visualize_relationship, visualize_residuals, visualize_slope_comparison, winsorize,
+    write_batch_summary, write_json, write_predictions, write_report, ArtistData, ChartStyle,
+    Column, ColumnConfig, CombinedSeries, CommaStyle, Corner, CsvSource, DataSource, DedupStrategy,
+    LogLevel, MissingPolicy, NegativePolicy, ParsedData, PlotOptions, RegressionResult,
+    RegressionSums, Relationship, RunSummary, SeriesColors, StatsBox, STDIN_PATH, THEIL_SEN_PAIRS,
+    VARIABLE_NAMES,
+};
+use std::collections::HashSet;
 use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, PartialEq)]
-struct ArtistData {
-    total_streams: f64,
-    solo_streams: f64,
-    feature_streams: f64,
-    lead_streams: f64,
-}
-fn parse_artist_data(file_path: &str) -> Result<Vec<ArtistData>, Box<dyn Error>> {
-    println!("Reading file from path: {}", file_path);
-    let mut reader = Reader::from_path(file_path)?;
-    let mut data_points = Vec::new();
-
-    for record in reader.records() {
-        let record = record?;
-
-        let total_streams: f64 = record
-            .get(1)
-            .unwrap_or("0")
-            .replace(',', "")
-            .parse()
-            .unwrap_or_else(|_| 0.0);
-        let solo_streams: f64 = record
-            .get(3)
-            .unwrap_or("0")
-            .replace(',', "")
-            .parse()
-            .unwrap_or_else(|_| 0.0);
-        let feature_streams: f64 = record
-            .get(5)
-            .unwrap_or("0")
-            .replace(',', "")
-            .parse()
-            .unwrap_or_else(|_| 0.0);
-        let lead_streams: f64 = record
-            .get(4)
-            .unwrap_or("0")
-            .replace(',', "")
-            .parse()
-            .unwrap_or_else(|_| 0.0);
-
-        data_points.push(ArtistData {
-            total_streams,
-            solo_streams,
-            feature_streams,
-            lead_streams,
-        });
+/// Relative difference allowed between an artist's total streams and the sum of its components.
+const CONSISTENCY_TOLERANCE: f64 = 0.01;
+
+/// Number of inconsistent artists listed by name in the data-quality warning.
+const LISTED_INCONSISTENCIES: usize = 5;
+
+/// Number of bins in the total streams histogram.
+const HISTOGRAM_BINS: usize = 20;
+
+/// Number of outlying points labeled with their artist name on each plot.
+const LABELED_OUTLIERS: usize = 5;
+
+/// Datasets with fewer records than this get every point labeled by --label-points.
+const LABEL_ALL_THRESHOLD: usize = 30;
+
+/// Whether rows with unparseable stream values are dropped instead of read as zeros.
+const SKIP_MALFORMED_ROWS: bool = true;
+
+/// Number of artists with the largest Cook's distance listed for each relationship.
+const LISTED_INFLUENTIAL: usize = 5;
+
+/// Default number of rows between parsing progress reports.
+const PROGRESS_INTERVAL: usize = 100_000;
+
+/// Confidence level of the bootstrap slope intervals.
+const BOOTSTRAP_LEVEL: f64 = 0.95;
+
+/// Residual skewness beyond which the residuals are described as skewed.
+const SKEW_THRESHOLD: f64 = 0.5;
+
+/// Residual excess kurtosis beyond which the tails are described as heavy or light.
+const KURTOSIS_THRESHOLD: f64 = 1.0;
+
+/// Describes the shape of a residual distribution, as a hint of whether the residuals are
+/// close enough to normal for the linear model's standard errors to be trusted.
+fn describe_residual_shape(skew: f64, kurtosis: f64) -> String {
+    let symmetry = if skew > SKEW_THRESHOLD {
+        "right-skewed"
+    } else if skew < -SKEW_THRESHOLD {
+        "left-skewed"
+    } else {
+        "roughly symmetric"
+    };
+    let tails = if kurtosis > KURTOSIS_THRESHOLD {
+        "heavy-tailed"
+    } else if kurtosis < -KURTOSIS_THRESHOLD {
+        "light-tailed"
+    } else {
+        "normal-tailed"
+    };
+    format!("residuals are {} and {}", symmetry, tails)
+}
+
+/// Fraction of values trimmed from each tail for the robust means in the summary.
+const TRIM_PROPORTION: f64 = 0.1;
+
+/// Smallest p-value reported exactly; the erfc approximation cannot resolve anything smaller.
+const P_VALUE_FLOOR: f64 = 1e-7;
+
+const USAGE: &str = "Usage: finalproject [OPTIONS]
+
+Options:
+  --input FILE            CSV dataset (.gz with the gzip feature), or - to read stdin (default: artists.csv);
+                          repeat to merge several files with the same columns
+  --batch DIR             Analyze every *.csv file in DIR, writing each file's outputs to a
+                          subdirectory of --outdir and one row per file to batch_summary.csv
+  --delimiter C           Field separator of the input file, or 'tab' (default: detected)
+  --negatives POLICY      keep, reject or clamp (to zero) records with negative stream values
+                          (default: keep, with a warning)
+  --missing POLICY        skip records with an empty stream value, or impute the column mean
+                          (default: skip)
+  --comma-style STYLE     comma-as-thousands (57,252.6) or comma-as-decimal (57252,6) in stream
+                          values (default: comma-as-thousands)
+  --outdir DIR            Directory the plots are written to, created if missing (default: .)
+  --output-prefix NAME    Prepend NAME_ to the name of every output file (default: none)
+  --outlier-threshold Z   Absolute z-score above which points are drawn as outliers (default: 3.0)
+  --drop-iqr-outliers     Remove points outside the IQR fences of x or y before fitting
+  --iqr-k K               Fence distance in IQRs used by --drop-iqr-outliers (default: 1.5)
+  --log-scale             Draw scatter plots with logarithmic axes
+  --poly-degree N         Also fit and draw a polynomial of degree N (e.g. 2 for quadratic)
+  --precision N           Decimal places of every printed regression number
+  --scientific            Print regression numbers in scientific notation
+  --width PX              Width of every chart in pixels (default: 1024)
+  --height PX             Height of every chart in pixels (default: 768)
+  --log-y                 Also fit ln(total streams) on x and draw the back-transformed curve
+  --hexbin BINS           Also draw each relationship as a density hexbin plot BINS hexagons wide
+  --moving-average N      Overlay the moving average of total streams over N artists ordered by x
+  --line-samples N        Segments fitted curves are drawn with across the x range (default: 200)
+  --point-radius PX       Radius of the points of scatter and residual plots (default: 5)
+  --point-alpha A         Opacity of those points between 0 and 1 (default: 1)
+  --title TEMPLATE        Caption of every relationship plot in place of its name; {x}, {y} and
+                          {n} stand for the axis names and the number of points
+  --title KEY=TEMPLATE    Caption of the plots of one relationship only (solo, feature, lead)
+  --stats-corner CORNER   Corner of the n/R²/r box on scatter plots, or 'none' (default: top-left)
+  --colors KEY=POINT,LINE Point and line colors (names or #rrggbb) of a relationship's scatter plot
+  --x-labels N            Number of labeled ticks on the x axis of scatter plots (default: 11)
+  --y-labels N            Number of labeled ticks on the y axis of scatter plots (default: 11)
+  --no-minor-grid         Omit the lighter minor gridlines from scatter plots
+  --theil-sen             Also fit and draw a robust Theil-Sen line
+  --theil-sen-pairs N     Most point pairs the Theil-Sen fit uses (default: 100000)
+  --no-intercept          Also fit and draw a regression line forced through the origin
+  --bootstrap N           Also report a bootstrap 95% slope CI from N resamples (seeded by --seed)
+  --compare-models        Also rank linear, log-y and quadratic fits of each relationship by AIC
+  --ridge LAMBDA          Also fit the multiple regression with an L2 penalty of LAMBDA on
+                          standardized predictors (0 gives ordinary least squares)
+  --quartiles             Also fit every relationship separately within each quartile of total
+                          streams and plot the four fits together
+  --confidence-band       Shade the 95% confidence band of the regression line on scatter plots
+  --jackknife             Also report the leave-one-out jackknife slope standard error and bias
+  --label-points          Label every scatter point with its artist name when there are fewer
+                          than 30 records (otherwise only the largest outliers are labeled)
+  --standardize           Also regress on z-score standardized variables
+  --weighted              Also fit a regression weighting each artist by total streams
+  --name-column NAME      Read artist names from the column with header NAME (default: 1st column)
+  --total NAME            Read total streams from the column with header NAME (default: 2nd)
+  --solo NAME             Read solo streams from the column with header NAME (default: 4th)
+  --feature NAME          Read feature streams from the column with header NAME (default: 6th)
+  --lead NAME             Read lead streams from the column with header NAME (default: 5th)
+  --group-column NAME     Also fit and plot every relationship separately for each value of NAME
+  --dedup STRATEGY        Merge rows of the same artist: keep 'first', 'last', or 'average' them
+  --sample N              Analyze a random sample of N records
+  --seed N                Seed of the --sample selection, for reproducible samples (default: random)
+  --min-streams N         Drop artists with fewer than N total streams right after parsing
+  --filter TEXT           Only analyze artists whose name contains TEXT (case-insensitive)
+  --predict X             Print the total streams predicted for predictor value X and exit
+  --highlight X           Mark the prediction at predictor value X on every scatter plot
+  --relationship NAME     Relationship used by --predict: solo, feature or lead (default: solo)
+  --predictions NAME      Write artist, actual, predicted, and residual total streams of one
+                          relationship (solo, feature or lead) to a CSV file named after its plot
+  --only NAME             Only analyze and plot one relationship: solo, feature or lead
+  --plots NAMES           Comma-separated relationships to analyze and plot (default: all)
+  --progress-every N      Report parsing progress every N rows (default: 100000)
+  -q, --quiet             Only print the analysis results, without progress or status messages
+  -v, --verbose           Also print skipped malformed rows and intermediate regression sums
+  --top-n N               Only analyze the N artists with the most total streams
+  --validate              Only parse the input and print its summary statistics, without plots
+  --max-skipped N         With --validate, fail if more than N rows are malformed
+  --list-columns          Print the zero-based index and name of every input column and exit
+  --streaming             Only print the regression lines, reading the file one record at a time
+  --help                  Print this message";
+
+/// Formats the final `SUMMARY` line as space-separated `key=value` pairs, with one
+/// `<relationship>_r2` entry per fitted relationship, for scripts to grep.
+fn format_summary(records: usize, skipped: usize, r_squared: &[(&str, f64)]) -> String {
+    let mut line = format!("SUMMARY records={} skipped={}", records, skipped);
+    for (key, value) in r_squared {
+        line.push_str(&format!(" {}_r2={:.4}", key, value));
     }
+    line
+}
 
-    println!("Successfully parsed {} valid records.", data_points.len());
-    Ok(data_points)
+/// Formats a p-value as `p ≈ 0.0123`, or `p < 1e-7` below `P_VALUE_FLOOR`.
+fn format_p_value(p: f64) -> String {
+    if p < P_VALUE_FLOOR {
+        format!("p < {:e}", P_VALUE_FLOOR)
+    } else {
+        format!("p ≈ {:.4}", p)
+    }
 }
 
-/// Calculates the linear regression line (slope and intercept).
-fn calculate_regression(data: &[(f64, f64)]) -> (f64, f64) {
-    let n = data.len() as f64;
-    let sum_x: f64 = data.iter().map(|(x, _)| *x).sum();
-    let sum_y: f64 = data.iter().map(|(_, y)| *y).sum();
-    let sum_xy: f64 = data.iter().map(|(x, y)| x * y).sum();
-    let sum_xx: f64 = data.iter().map(|(x, _)| x * x).sum();
+/// How regression numbers are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct NumberFormat {
+    /// Decimal places of every number, overriding each printout's own default.
+    precision: Option<usize>,
+    /// Print numbers in scientific notation, such as `1.29e0`.
+    scientific: bool,
+}
 
-    let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_xx - sum_x * sum_x);
-    let intercept = (sum_y - slope * sum_x) / n;
+impl NumberFormat {
+    /// Formats `value` with the configured precision, or `default_precision` decimal places
+    /// when none was given.
+    fn format(&self, value: f64, default_precision: usize) -> String {
+        let precision = self.precision.unwrap_or(default_precision);
+        if self.scientific {
+            format!("{:.*e}", precision, value)
+        } else {
+            format!("{:.*}", precision, value)
+        }
+    }
+}
 
-    (slope, intercept)
+/// Options controlling a single run of the program.
+#[derive(Debug, Clone, PartialEq)]
+struct CliOptions {
+    inputs: Vec<String>,
+    batch: Option<PathBuf>,
+    delimiter: Option<u8>,
+    comma_style: CommaStyle,
+    negatives: NegativePolicy,
+    missing: MissingPolicy,
+    outdir: PathBuf,
+    /// Prepended, with an underscore, to the name of every output file when not empty.
+    output_prefix: String,
+    outlier_threshold: f64,
+    drop_iqr_outliers: bool,
+    iqr_k: f64,
+    log_scale: bool,
+    poly_degree: Option<usize>,
+    moving_average: Option<usize>,
+    /// Hexagons across the x range of the hexbin density plots, if drawn.
+    hexbin: Option<usize>,
+    style: ChartStyle,
+    number_format: NumberFormat,
+    /// Corner of the stats box on scatter plots, or `None` to omit it.
+    stats_corner: Option<Corner>,
+    title: Option<String>,
+    relationship_titles: Vec<(String, String)>,
+    log_y: bool,
+    /// Fit a Theil-Sen line from at most this many point pairs.
+    theil_sen: Option<usize>,
+    no_intercept: bool,
+    /// Number of resamples of the bootstrap slope interval.
+    bootstrap: Option<usize>,
+    standardize: bool,
+    label_points: bool,
+    jackknife: bool,
+    confidence_band: bool,
+    quartiles: bool,
+    ridge: Option<f64>,
+    compare_models: bool,
+    weighted: bool,
+    group_column: Option<String>,
+    /// Header names of the name, total, solo, feature, and lead columns, overriding the
+    /// default positions.
+    name_column: Option<String>,
+    total_column: Option<String>,
+    solo_column: Option<String>,
+    feature_column: Option<String>,
+    lead_column: Option<String>,
+    dedup: Option<DedupStrategy>,
+    sample: Option<usize>,
+    seed: Option<u64>,
+    /// Floor on total streams below which records are dropped after parsing.
+    min_streams: Option<f64>,
+    filter: Option<String>,
+    top_n: Option<usize>,
+    list_columns: bool,
+    streaming: bool,
+    validate: bool,
+    max_skipped: Option<usize>,
+    progress_every: usize,
+    log_level: LogLevel,
+    predict: Option<f64>,
+    highlight: Option<f64>,
+    relationship: String,
+    /// Keys of the relationships to analyze; empty means all of them.
+    only: Vec<String>,
+    /// Relationship whose per-artist predictions are written to a CSV file.
+    predictions: Option<String>,
 }
 
-/// Visualizes the scatter plot with a regression line.
-fn visualize_relationship(
-    data: &[(f64, f64)],
-    slope: f64,
-    intercept: f64,
-    title: &str,
-    file_name: &str,
-) -> Result<(), Box<dyn Error>> {
-    let root = BitMapBackend::new(file_name, (1024, 768)).into_drawing_area();
-    root.fill(&WHITE)?;
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            inputs: vec!["artists.csv".to_string()],
+            batch: None,
+            delimiter: None,
+            comma_style: CommaStyle::Thousands,
+            negatives: NegativePolicy::Keep,
+            missing: MissingPolicy::Skip,
+            outdir: PathBuf::from("."),
+            output_prefix: String::new(),
+            outlier_threshold: 3.0,
+            drop_iqr_outliers: false,
+            iqr_k: 1.5,
+            log_scale: false,
+            poly_degree: None,
+            moving_average: None,
+            hexbin: None,
+            style: ChartStyle::default(),
+            number_format: NumberFormat::default(),
+            stats_corner: Some(Corner::TopLeft),
+            title: None,
+            relationship_titles: Vec::new(),
+            log_y: false,
+            theil_sen: None,
+            no_intercept: false,
+            bootstrap: None,
+            standardize: false,
+            label_points: false,
+            jackknife: false,
+            confidence_band: false,
+            quartiles: false,
+            ridge: None,
+            compare_models: false,
+            weighted: false,
+            group_column: None,
+            name_column: None,
+            total_column: None,
+            solo_column: None,
+            feature_column: None,
+            lead_column: None,
+            dedup: None,
+            sample: None,
+            seed: None,
+            min_streams: None,
+            filter: None,
+            top_n: None,
+            list_columns: false,
+            streaming: false,
+            validate: false,
+            max_skipped: None,
+            progress_every: PROGRESS_INTERVAL,
+            log_level: LogLevel::Normal,
+            predict: None,
+            highlight: None,
+            relationship: "solo".to_string(),
+            only: Vec::new(),
+            predictions: None,
+        }
+    }
+}
 
-    let max_x = data.iter().map(|(x, _)| *x).fold(0.0 / 0.0, f64::max);
-    let max_y = data.iter().map(|(_, y)| *y).fold(0.0 / 0.0, f64::max);
+impl CliOptions {
+    /// Column mapping and value handling for reading the input, with any columns named on
+    /// the command line looked up in the header and the rest at their default positions.
+    fn column_config(&self) -> ColumnConfig {
+        let defaults = ColumnConfig::default();
+        let named =
+            |name: &Option<String>, default: Column| name.clone().map_or(default, Column::Name);
+        ColumnConfig {
+            name: named(&self.name_column, defaults.name),
+            total: named(&self.total_column, defaults.total),
+            solo: named(&self.solo_column, defaults.solo),
+            feature: named(&self.feature_column, defaults.feature),
+            lead: named(&self.lead_column, defaults.lead),
+            group: self.group_column.clone().map(Column::Name),
+            comma: self.comma_style,
+            negatives: self.negatives,
+            missing: self.missing,
+        }
+    }
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption(title, ("sans-serif", 40))
-        .margin(20)
-        .x_label_area_size(40)
-        .y_label_area_size(40)
-        .build_cartesian_2d(0.0..max_x, 0.0..max_y)?;
+    /// Path in the output directory of the output file `file_name`, with the output prefix.
+    fn output_path(&self, file_name: &str) -> PathBuf {
+        if self.output_prefix.is_empty() {
+            self.outdir.join(file_name)
+        } else {
+            self.outdir
+                .join(format!("{}_{}", self.output_prefix, file_name))
+        }
+    }
+}
 
-    chart.configure_mesh().x_desc("X").y_desc("Y").draw()?;
+/// Lowercases `text` and replaces every character that is not ASCII alphanumeric with an
+/// underscore, so it can be used in a file name.
+fn file_stem_part(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
 
-    chart.draw_series(data.iter().map(|(x, y)| Circle::new((*x, *y), 5, RED.filled())))?;
+/// Parses a delimiter argument, which must be a single ASCII character or `tab`.
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err(format!(
+            "--delimiter expects a single character or 'tab', got '{}'",
+            value
+        )),
+    }
+}
 
-    chart.draw_series(LineSeries::new(
-        (0..=max_x as i32).map(|x| {
-            let x = x as f64;
-            let y = slope * x + intercept;
-            (x, y)
-        }),
-        &BLUE,
-    ))?
-    .label(format!("y = {:.2}x + {:.2}", slope, intercept))
-    .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], &BLUE));
-
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
-
-    println!("Scatter plot saved to {}", file_name);
-    Ok(())
+/// Parses a `--colors` argument of the form `KEY=POINT,LINE`, such as `lead=purple,#008080`.
+fn parse_series_colors(value: &str) -> Result<(String, SeriesColors), String> {
+    let invalid = || format!("--colors expects KEY=POINT,LINE, got '{}'", value);
+    let (key, colors) = value.split_once('=').ok_or_else(invalid)?;
+    let (point, line) = colors.split_once(',').ok_or_else(invalid)?;
+    select_relationships(&[key.to_string()])?;
+    Ok((
+        key.to_string(),
+        SeriesColors {
+            point: parse_color(point)?,
+            line: parse_color(line)?,
+        },
+    ))
 }
 
-/// Main function
-fn main() {
-    let file_path = "artists.csv";
+/// Parses command-line arguments (excluding the program name).
+/// Returns `Ok(None)` when help was requested.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<CliOptions>, String> {
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter();
+
+    let mut explicit_input = false;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => {
+                if !explicit_input {
+                    options.inputs.clear();
+                    explicit_input = true;
+                }
+                options.inputs.push(value()?)
+            }
+            "--batch" => options.batch = Some(PathBuf::from(value()?)),
+            "--delimiter" => options.delimiter = Some(parse_delimiter(&value()?)?),
+            "--comma-style" => options.comma_style = value()?.parse()?,
+            "--negatives" => options.negatives = value()?.parse()?,
+            "--missing" => options.missing = value()?.parse()?,
+            "--outdir" => options.outdir = PathBuf::from(value()?),
+            "--output-prefix" => {
+                options.output_prefix = Some(value()?)
+                    .filter(|prefix| !prefix.contains(['/', '\\']))
+                    .ok_or_else(|| "--output-prefix must not contain path separators".to_string())?
+            }
+            "--outlier-threshold" => {
+                options.outlier_threshold = value()?
+                    .parse()
+                    .map_err(|_| "--outlier-threshold expects a number".to_string())?
+            }
+            "--drop-iqr-outliers" => options.drop_iqr_outliers = true,
+            "--iqr-k" => {
+                options.iqr_k = value()?
+                    .parse()
+                    .ok()
+                    .filter(|k: &f64| *k >= 0.0)
+                    .ok_or_else(|| "--iqr-k expects a non-negative number".to_string())?
+            }
+            "--log-scale" => options.log_scale = true,
+            "--poly-degree" => {
+                options.poly_degree = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--poly-degree expects a non-negative integer".to_string())?,
+                )
+            }
+            "--hexbin" => {
+                options.hexbin = Some(
+                    value()?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| "--hexbin expects a positive integer".to_string())?,
+                )
+            }
+            "--moving-average" => {
+                options.moving_average =
+                    Some(
+                        value()?.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                            "--moving-average expects a positive integer".to_string()
+                        })?,
+                    )
+            }
+            "--precision" => {
+                options.number_format.precision = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--precision expects a non-negative integer".to_string())?,
+                )
+            }
+            "--scientific" => options.number_format.scientific = true,
+            "--width" => {
+                options.style.width = value()?
+                    .parse()
+                    .map_err(|_| "--width expects a positive integer".to_string())?
+            }
+            "--height" => {
+                options.style.height = value()?
+                    .parse()
+                    .map_err(|_| "--height expects a positive integer".to_string())?
+            }
+            "--log-y" => options.log_y = true,
+            "--line-samples" => {
+                options.style.line_samples = value()?
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| "--line-samples expects a positive integer".to_string())?
+            }
+            "--point-radius" => {
+                options.style.point_radius = value()?
+                    .parse()
+                    .map_err(|_| "--point-radius expects a non-negative integer".to_string())?
+            }
+            "--point-alpha" => {
+                options.style.point_alpha = value()?
+                    .parse()
+                    .ok()
+                    .filter(|a| (0.0..=1.0).contains(a))
+                    .ok_or_else(|| "--point-alpha expects a number between 0 and 1".to_string())?
+            }
+            "--stats-corner" => {
+                options.stats_corner = match value()?.as_str() {
+                    "none" => None,
+                    corner => Some(corner.parse()?),
+                }
+            }
+            "--title" => {
+                let template = value()?;
+                match template.split_once('=') {
+                    Some((key, title)) if select_relationships(&[key.to_string()]).is_ok() => {
+                        options
+                            .relationship_titles
+                            .push((key.to_string(), title.to_string()))
+                    }
+                    _ => options.title = Some(template),
+                }
+            }
+            "--colors" => {
+                let (key, colors) = parse_series_colors(&value()?)?;
+                options.style.palette.set(&key, colors);
+            }
+            "--x-labels" => {
+                options.style.mesh.x_labels = value()?
+                    .parse()
+                    .map_err(|_| "--x-labels expects a non-negative integer".to_string())?
+            }
+            "--y-labels" => {
+                options.style.mesh.y_labels = value()?
+                    .parse()
+                    .map_err(|_| "--y-labels expects a non-negative integer".to_string())?
+            }
+            "--no-minor-grid" => options.style.mesh.minor_grid = false,
+            "--theil-sen" => options.theil_sen = Some(options.theil_sen.unwrap_or(THEIL_SEN_PAIRS)),
+            "--theil-sen-pairs" => {
+                options.theil_sen =
+                    Some(value()?.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                        "--theil-sen-pairs expects a positive integer".to_string()
+                    })?)
+            }
+            "--no-intercept" => options.no_intercept = true,
+            "--bootstrap" => {
+                options.bootstrap = Some(
+                    value()?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| "--bootstrap expects a positive integer".to_string())?,
+                )
+            }
+            "--standardize" => options.standardize = true,
+            "--label-points" => options.label_points = true,
+            "--jackknife" => options.jackknife = true,
+            "--confidence-band" => options.confidence_band = true,
+            "--quartiles" => options.quartiles = true,
+            "--ridge" => {
+                options.ridge = Some(
+                    value()?
+                        .parse()
+                        .ok()
+                        .filter(|lambda: &f64| lambda.is_finite() && *lambda >= 0.0)
+                        .ok_or_else(|| "--ridge expects a non-negative number".to_string())?,
+                )
+            }
+            "--compare-models" => options.compare_models = true,
+            "--weighted" => options.weighted = true,
+            "--group-column" => options.group_column = Some(value()?),
+            "--name-column" => options.name_column = Some(value()?),
+            "--total" => options.total_column = Some(value()?),
+            "--solo" => options.solo_column = Some(value()?),
+            "--feature" => options.feature_column = Some(value()?),
+            "--lead" => options.lead_column = Some(value()?),
+            "--dedup" => options.dedup = Some(value()?.parse()?),
+            "--sample" => {
+                options.sample = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--sample expects a non-negative integer".to_string())?,
+                )
+            }
+            "--seed" => {
+                options.seed = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--seed expects a non-negative integer".to_string())?,
+                )
+            }
+            "--min-streams" => {
+                options.min_streams = Some(
+                    value()?
+                        .parse()
+                        .ok()
+                        .filter(|n: &f64| n.is_finite())
+                        .ok_or_else(|| "--min-streams expects a number".to_string())?,
+                )
+            }
+            "--filter" => options.filter = Some(value()?),
+            "--top-n" => {
+                options.top_n = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--top-n expects a non-negative integer".to_string())?,
+                )
+            }
+            "--list-columns" => options.list_columns = true,
+            "--streaming" => options.streaming = true,
+            "--validate" => options.validate = true,
+            "--max-skipped" => {
+                options.max_skipped = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--max-skipped expects a non-negative integer".to_string())?,
+                )
+            }
+            "--progress-every" => {
+                options.progress_every = value()?
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| "--progress-every expects a positive integer".to_string())?
+            }
+            "--quiet" | "-q" => options.log_level = LogLevel::Quiet,
+            "--verbose" | "-v" => options.log_level = LogLevel::Verbose,
+            "--predict" => {
+                options.predict = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--predict expects a number".to_string())?,
+                )
+            }
+            "--highlight" => {
+                options.highlight = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "--highlight expects a number".to_string())?,
+                )
+            }
+            "--relationship" => options.relationship = value()?,
+            "--only" => options.only.push(value()?),
+            "--predictions" => options.predictions = Some(value()?),
+            "--plots" => options
+                .only
+                .extend(value()?.split(',').map(|key| key.trim().to_string())),
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(format!("unrecognized argument '{}'", arg)),
+        }
+    }
+
+    if options.batch.is_some() && explicit_input {
+        return Err("--batch cannot be combined with --input".to_string());
+    }
+    select_relationships(&options.only)?;
+    select_relationships(std::slice::from_ref(&options.relationship))?;
+    if let Some(key) = &options.predictions {
+        select_relationships(std::slice::from_ref(key))?;
+    }
+    Ok(Some(options))
+}
+
+/// A relationship's fitted statistics and the points they were fitted on.
+type FittedRelationship = (RegressionResult, Vec<(f64, f64)>);
 
-    let data = match parse_artist_data(file_path) {
-        Ok(data) => data,
+/// Fits, reports, and plots a single relationship, returning its statistics with the
+/// points they were fitted on (after any `--drop-iqr-outliers` filtering), or `None` if
+/// no regression could be fitted.
+fn analyze_relationship(
+    relationship: &Relationship,
+    data: &[ArtistData],
+    group: Option<&str>,
+    options: &CliOptions,
+) -> Result<Option<FittedRelationship>, Box<dyn Error>> {
+    let (title, file_stem) = match group {
+        Some(group) => (
+            format!("{} [{}]", relationship.name, group),
+            format!("{}_{}", relationship.file_stem, file_stem_part(group)),
+        ),
+        None => (
+            relationship.name.to_string(),
+            relationship.file_stem.to_string(),
+        ),
+    };
+    let kept: Vec<ArtistData>;
+    let data = if options.drop_iqr_outliers {
+        let points: Vec<(f64, f64)> = data.iter().map(relationship.extract).collect();
+        let xs: Vec<f64> = points.iter().map(|(x, _)| *x).collect();
+        let ys: Vec<f64> = points.iter().map(|(_, y)| *y).collect();
+        let outlier_x = filter_iqr_outliers(&xs, options.iqr_k);
+        let outlier_y = filter_iqr_outliers(&ys, options.iqr_k);
+        kept = data
+            .iter()
+            .zip(outlier_x.iter().zip(&outlier_y))
+            .filter(|(_, (x, y))| !**x && !**y)
+            .map(|(d, _)| d.clone())
+            .collect();
+        info!(
+            "{title}: removed {} IQR outliers (k = {}) before fitting",
+            data.len() - kept.len(),
+            options.iqr_k
+        );
+        &kept[..]
+    } else {
+        data
+    };
+    let relationship_data: Vec<(f64, f64)> = data.iter().map(relationship.extract).collect();
+    let (slope, intercept) = match calculate_regression(&relationship_data) {
+        Ok(fit) => fit,
         Err(e) => {
-            eprintln!("Error parsing dataset: {}", e);
-            return;
+            status!("{title}: cannot fit regression: {e}");
+            return Ok(None);
         }
     };
-
-    let relationships: Vec<(&str, Box<dyn Fn(&ArtistData) -> (f64, f64)>)> = vec![
-        (
-            "Total Streams vs Solo Streams",
-            Box::new(|d: &ArtistData| (d.solo_streams, d.total_streams)),
+    if log_enabled(LogLevel::Verbose) {
+        let mut sums = RegressionSums::default();
+        for (x, y) in &relationship_data {
+            sums.push(*x, *y);
+        }
+        debug!(
+            "{title} sums: n = {}, Σx = {}, Σy = {}, Σxy = {}, Σx² = {}",
+            sums.n, sums.sum_x, sums.sum_y, sums.sum_xy, sums.sum_xx
+        );
+    }
+    let r_squared = calculate_r_squared(&relationship_data, slope, intercept)?;
+    let num = |value: f64, default_precision: usize| {
+        options.number_format.format(value, default_precision)
+    };
+    let n = relationship_data.len();
+    println!(
+        "{title} Regression: y = {}x + {}, R² = {}, adjusted R² = {}",
+        num(slope, 2),
+        num(intercept, 2),
+        num(r_squared, 4),
+        num(adjusted_r_squared(r_squared, n, 1), 4)
+    );
+    let beta = standardized_slope(&relationship_data, slope);
+    match beta {
+        Ok(beta) => println!(
+            "{title} Coefficients: raw slope = {}, standardized β = {}",
+            num(slope, 4),
+            num(beta, 4)
         ),
-        (
-            "Total Streams vs Featured Streams",
-            Box::new(|d: &ArtistData| (d.feature_streams, d.total_streams)),
+        Err(ref e) => status!("{title}: cannot standardize slope: {e}"),
+    }
+    match elasticity(&relationship_data) {
+        Ok((value, dropped)) => {
+            println!(
+                "{title} Elasticity (log-log slope, % change in {} per 1% change in {}): {}",
+                relationship.y_label,
+                relationship.x_label,
+                num(value, 4)
+            );
+            if dropped > 0 {
+                info!("{title}: dropped {dropped} points with non-positive values from the elasticity fit");
+            }
+        }
+        Err(e) => status!("{title}: cannot compute elasticity: {e}"),
+    }
+    let mae = mean_absolute_error(&relationship_data, slope, intercept)?;
+    let rmse = root_mean_squared_error(&relationship_data, slope, intercept)?;
+    println!(
+        "{title} Errors: MAE = {}, RMSE = {}",
+        num(mae, 2),
+        num(rmse, 2)
+    );
+    let significance = f_test(&relationship_data, slope, intercept);
+    match significance {
+        Ok((f, p)) => println!("{title} F-test: F = {f:.2}, {}", format_p_value(p)),
+        Err(ref e) => status!("{title}: cannot run F-test: {e}"),
+    }
+    match slope_confidence_interval(&relationship_data, slope, intercept) {
+        Ok((lower, upper)) => println!(
+            "{title} Slope 95% CI: [{}, {}]",
+            num(lower, 4),
+            num(upper, 4)
         ),
-        (
-            "Total Streams vs Lead Streams",
-            Box::new(|d: &ArtistData| (d.lead_streams, d.total_streams)),
+        Err(e) => status!("{title}: cannot compute slope CI: {e}"),
+    }
+    if let Some(iterations) = options.bootstrap {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        match bootstrap_slope_ci(&relationship_data, iterations, BOOTSTRAP_LEVEL, seed) {
+            Ok((lower, upper)) => println!(
+                "{title} Bootstrap {}% slope CI ({iterations} resamples, seed {seed}): [{}, {}]",
+                BOOTSTRAP_LEVEL * 100.0,
+                num(lower, 4),
+                num(upper, 4)
+            ),
+            Err(e) => status!("{title}: cannot bootstrap slope CI: {e}"),
+        }
+    }
+    if options.jackknife {
+        match jackknife_slope(&relationship_data) {
+            Ok((standard_error, bias)) => println!(
+                "{title} Jackknife slope: SE = {}, bias = {}",
+                num(standard_error, 4),
+                num(bias, 4)
+            ),
+            Err(e) => status!("{title}: cannot run jackknife: {e}"),
+        }
+    }
+    let correlation = pearson_correlation(&relationship_data)?;
+    if options.standardize {
+        let xs: Vec<f64> = relationship_data.iter().map(|(x, _)| *x).collect();
+        let ys: Vec<f64> = relationship_data.iter().map(|(_, y)| *y).collect();
+        let standardized: Vec<(f64, f64)> =
+            standardize(&xs).into_iter().zip(standardize(&ys)).collect();
+        if let Ok((beta, alpha)) = calculate_regression(&standardized) {
+            println!(
+                "{title} Standardized Regression: z_y = {} z_x + {}",
+                num(beta, 4),
+                num(alpha, 4)
+            );
+        }
+    }
+    if options.weighted {
+        let weights: Vec<f64> = data.iter().map(|d| d.total_streams).collect();
+        match calculate_weighted_regression(&relationship_data, &weights) {
+            Ok((w_slope, w_intercept)) => {
+                println!(
+                    "{title} Weighted Regression: y = {}x + {}",
+                    num(w_slope, 2),
+                    num(w_intercept, 2)
+                )
+            }
+            Err(e) => status!("{title}: cannot fit weighted regression: {e}"),
+        }
+    }
+    let mut influence: Vec<(usize, f64)> =
+        match cooks_distance(&relationship_data, slope, intercept) {
+            Ok(distances) => distances.into_iter().enumerate().collect(),
+            Err(e) => {
+                status!("{title}: cannot compute Cook's distance: {e}");
+                Vec::new()
+            }
+        };
+    influence.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let influential: Vec<String> = influence
+        .iter()
+        .take(LISTED_INFLUENTIAL)
+        .map(|(i, d)| format!("{} ({:.3})", data[*i].name, d))
+        .collect();
+    if !influential.is_empty() {
+        println!(
+            "{title} Most influential (Cook's distance): {}",
+            influential.join(", ")
+        );
+    }
+    let rank_correlation = spearman_correlation(&relationship_data)?;
+    println!(
+        "{title} Correlation: Pearson r = {}, Spearman rho = {}",
+        num(correlation, 4),
+        num(rank_correlation, 4)
+    );
+    let result = RegressionResult {
+        name: title.clone(),
+        slope,
+        standardized_slope: beta.ok(),
+        intercept,
+        r_squared,
+        correlation,
+        f_statistic: significance.as_ref().ok().map(|(f, _)| *f),
+        p_value: significance.as_ref().ok().map(|(_, p)| *p),
+    };
+
+    let file_name = options.output_path(&format!("{}.png", file_stem));
+    let file_name = file_name.to_string_lossy();
+
+    let label_all = options.label_points && relationship_data.len() < LABEL_ALL_THRESHOLD;
+    if options.label_points && !label_all {
+        info!(
+            "{title}: {} points are too many to label; labeling only the largest outliers",
+            relationship_data.len()
+        );
+    }
+    let labeled: Vec<usize> = if label_all {
+        (0..relationship_data.len()).collect()
+    } else {
+        top_outlier_indices(&relationship_data, slope, intercept, LABELED_OUTLIERS)
+    };
+    let labels: Vec<((f64, f64), String)> = labeled
+        .into_iter()
+        .map(|i| (relationship_data[i], data[i].name.clone()))
+        .collect();
+
+    let xs: Vec<f64> = relationship_data.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<f64> = relationship_data.iter().map(|(_, y)| *y).collect();
+    let mut outliers = find_outliers(&xs, options.outlier_threshold);
+    outliers.extend(find_outliers(&ys, options.outlier_threshold));
+    outliers.sort_unstable();
+    outliers.dedup();
+    println!(
+        "{title}: {} outliers with |z| > {}",
+        outliers.len(),
+        options.outlier_threshold
+    );
+
+    let polynomial = options.poly_degree.and_then(|degree| {
+        let fit = calculate_polynomial_regression(&relationship_data, degree);
+        match &fit {
+            Ok(coefficients) => println!(
+                "{title} Polynomial Regression (degree {degree}): {}",
+                format_polynomial(coefficients)
+            ),
+            Err(e) => status!("{title}: cannot fit polynomial of degree {degree}: {e}"),
+        }
+        fit.ok()
+    });
+
+    let exponential = if options.log_y {
+        let (transformed, dropped) = log_transform_y(&relationship_data);
+        if dropped > 0 {
+            status!("{title}: dropped {dropped} points with non-positive y from the log-y fit");
+        }
+        let fit = calculate_regression(&transformed);
+        match &fit {
+            Ok((log_slope, log_intercept)) => {
+                println!(
+                    "{title} Log-y Regression: ln(y) = {log_slope:.6e}x + {log_intercept:.4}, i.e. y = {:.2} * e^({log_slope:.6e}x)",
+                    log_intercept.exp()
+                );
+            }
+            Err(e) => status!("{title}: cannot fit log-y regression: {e}"),
+        }
+        fit.ok()
+    } else {
+        None
+    };
+
+    if options.compare_models {
+        match compare_models(&relationship_data) {
+            Ok(fits) => {
+                println!("{title} Model comparison (lower AIC is better):");
+                for (rank, fit) in fits.iter().enumerate() {
+                    println!(
+                        "  {}. {:<20} k = {}, R² = {}, adjusted R² = {}, AIC = {}",
+                        rank + 1,
+                        fit.name,
+                        fit.parameters,
+                        num(fit.r_squared, 4),
+                        num(adjusted_r_squared(fit.r_squared, n, fit.parameters - 1), 4),
+                        num(fit.aic, 2)
+                    );
+                }
+                println!("{title} Best model by AIC: {}", fits[0].name);
+            }
+            Err(e) => status!("{title}: cannot compare models: {e}"),
+        }
+    }
+
+    let robust = options.theil_sen.and_then(|max_pairs| {
+        let fit = theil_sen(&relationship_data, max_pairs);
+        match fit {
+            Ok((robust_slope, robust_intercept)) => println!(
+                "{title} Theil-Sen Regression: y = {}x + {}",
+                num(robust_slope, 2),
+                num(robust_intercept, 2)
+            ),
+            Err(ref e) => status!("{title}: cannot fit Theil-Sen line: {e}"),
+        }
+        fit.ok()
+    });
+
+    let origin_slope = if options.no_intercept {
+        match calculate_regression_no_intercept(&relationship_data) {
+            Ok(origin_slope) => {
+                let origin_r_squared = calculate_r_squared(&relationship_data, origin_slope, 0.0)?;
+                println!(
+                    "{title} Regression through origin: y = {}x, R² = {}, adjusted R² = {} (unconstrained: y = {}x + {}, R² = {})",
+                    num(origin_slope, 2),
+                    num(origin_r_squared, 4),
+                    num(adjusted_r_squared(origin_r_squared, n, 1), 4),
+                    num(slope, 2),
+                    num(intercept, 2),
+                    num(r_squared, 4)
+                );
+                Some(origin_slope)
+            }
+            Err(e) => {
+                status!("{title}: cannot fit regression through origin: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let smoothed = match options.moving_average {
+        Some(window) => {
+            // Log axes cannot show non-positive values, so leave them out of the average too.
+            let mut sorted: Vec<(f64, f64)> = relationship_data
+                .iter()
+                .copied()
+                .filter(|(x, y)| !options.log_scale || (*x > 0.0 && *y > 0.0))
+                .collect();
+            sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let ys: Vec<f64> = sorted.iter().map(|(_, y)| *y).collect();
+            let averages = moving_average(&ys, window)?;
+            Some(sorted.iter().map(|(x, _)| *x).zip(averages).collect())
+        }
+        None => None,
+    };
+
+    let caption = options
+        .relationship_titles
+        .iter()
+        .find(|(key, _)| key == relationship.key)
+        .map(|(_, template)| template)
+        .or(options.title.as_ref())
+        .map(|template| {
+            let caption = expand_title(
+                template,
+                relationship.x_label,
+                relationship.y_label,
+                relationship_data.len(),
+            );
+            match group {
+                Some(group) => format!("{} [{}]", caption, group),
+                None => caption,
+            }
+        });
+    let plot_title = caption.clone().unwrap_or_else(|| title.clone());
+
+    let band = if options.confidence_band {
+        let samples = options.style.line_samples;
+        let band_data: Vec<(f64, f64)> = relationship_data
+            .iter()
+            .copied()
+            .filter(|(x, y)| !options.log_scale || (*x > 0.0 && *y > 0.0))
+            .collect();
+        match confidence_band(&band_data, slope, intercept, samples) {
+            Ok(band) => Some(band),
+            Err(e) => {
+                status!("{title}: cannot compute confidence band: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let plot_options = PlotOptions {
+        labels,
+        outliers,
+        log_scale: options.log_scale,
+        polynomial,
+        exponential,
+        robust,
+        highlight: options.highlight.map(|x| (x, predict(slope, intercept, x))),
+        smoothed,
+        colors: options.style.palette.colors(relationship.key),
+        origin_slope,
+        stats_box: options.stats_corner.map(|_| StatsBox {
+            n: relationship_data.len(),
+            r_squared,
+            correlation,
+        }),
+        stats_corner: options.stats_corner.unwrap_or_default(),
+        title: caption,
+        confidence_band: band,
+    };
+
+    visualize_relationship(
+        &relationship_data,
+        &result,
+        &plot_options,
+        &options.style,
+        relationship.x_label,
+        relationship.y_label,
+        &file_name,
+    )
+    .map_err(|e| {
+        eprintln!("Error generating plot for {title}: {e}");
+        e
+    })?;
+
+    if let Some(bins) = options.hexbin {
+        let hexbin_file = options.output_path(&format!("{}_hexbin.png", file_stem));
+        visualize_hexbin(
+            &relationship_data,
+            bins,
+            &format!("{plot_title} Density"),
+            relationship.x_label,
+            relationship.y_label,
+            &options.style,
+            &hexbin_file.to_string_lossy(),
+        )
+        .map_err(|e| {
+            eprintln!("Error generating hexbin plot for {title}: {e}");
+            e
+        })?;
+    }
+
+    if options.quartiles {
+        let mut fits = Vec::new();
+        for (i, quartile) in quartiles_by_total(data.to_vec()).iter().enumerate() {
+            let (Some(smallest), Some(largest)) = (quartile.first(), quartile.last()) else {
+                continue;
+            };
+            let label = format!(
+                "Q{} (total {}-{})",
+                i + 1,
+                format_compact(smallest.total_streams),
+                format_compact(largest.total_streams)
+            );
+            let points: Vec<(f64, f64)> = quartile.iter().map(relationship.extract).collect();
+            match calculate_regression(&points) {
+                Ok((q_slope, q_intercept)) => {
+                    println!(
+                        "{title} {label}: slope = {}, intercept = {}, n = {}",
+                        num(q_slope, 4),
+                        num(q_intercept, 2),
+                        points.len()
+                    );
+                    fits.push((label, points, q_slope, q_intercept));
+                }
+                Err(e) => status!("{title} {label}: cannot fit regression: {e}"),
+            }
+        }
+        let series: Vec<CombinedSeries> = fits
+            .iter()
+            .map(|(label, points, q_slope, q_intercept)| {
+                (label.as_str(), points.clone(), *q_slope, *q_intercept)
+            })
+            .collect();
+        let quartile_file = options.output_path(&format!("{}_quartiles.png", file_stem));
+        visualize_quartiles(
+            &series,
+            &format!("{plot_title} by Total Streams Quartile"),
+            relationship.x_label,
+            relationship.y_label,
+            &options.style,
+            &quartile_file.to_string_lossy(),
+        )
+        .map_err(|e| {
+            eprintln!("Error generating quartile plot for {title}: {e}");
+            e
+        })?;
+    }
+
+    if options.predictions.as_deref() == Some(relationship.key) {
+        let names: Vec<&str> = data.iter().map(|d| d.name.as_str()).collect();
+        let predictions_file = options.output_path(&format!("{}_predictions.csv", file_stem));
+        write_predictions(
+            &predictions_file.to_string_lossy(),
+            &relationship_data,
+            slope,
+            intercept,
+            &names,
+        )?;
+    }
+
+    let residuals = compute_residuals(&relationship_data, slope, intercept)?;
+    match (skewness(&residuals), excess_kurtosis(&residuals)) {
+        (Ok(skew), Ok(kurtosis)) => println!(
+            "{title} Residual skewness = {}, excess kurtosis = {} ({})",
+            num(skew, 2),
+            num(kurtosis, 2),
+            describe_residual_shape(skew, kurtosis)
         ),
-    ];
+        (Err(e), _) | (_, Err(e)) => status!("{title}: cannot check residual normality: {e}"),
+    }
+    let residual_title = format!("{plot_title} Residuals");
+    let residual_file = options.output_path(&format!("{}_residuals.png", file_stem));
+
+    visualize_residuals(
+        &relationship_data,
+        &residuals,
+        relationship.key,
+        &residual_title,
+        relationship.x_label,
+        &options.style,
+        &residual_file.to_string_lossy(),
+    )
+    .map_err(|e| {
+        eprintln!("Error generating residual plot for {title}: {e}");
+        e
+    })?;
+
+    Ok(Some((result, relationship_data)))
+}
+
+/// Main function
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", USAGE);
+            return Err(e.into());
+        }
+    };
+    match &options.batch {
+        Some(dir) => run_batch(&options, dir),
+        None => run(&options).map(|_| ()),
+    }
+}
+
+/// Runs the whole analysis described by `options`.
+fn run(options: &CliOptions) -> Result<Option<RunSummary>, Box<dyn Error>> {
+    set_log_level(options.log_level);
+    if let Some(missing) = options
+        .inputs
+        .iter()
+        .find(|input| *input != STDIN_PATH && !Path::new(input).is_file())
+    {
+        eprintln!("{}", USAGE);
+        return Err(format!("input file '{}' not found", missing).into());
+    }
+
+    if options.list_columns {
+        for input in &options.inputs {
+            if options.inputs.len() > 1 {
+                println!("{}:", input);
+            }
+            let mut reader = open_input(input, options.delimiter)?;
+            for (index, name) in reader.headers()?.iter().enumerate() {
+                println!("{:>4}  {}", index, name.trim());
+            }
+        }
+        return Ok(None);
+    }
+
+    if !options.validate {
+        fs::create_dir_all(&options.outdir).map_err(|e| {
+            eprintln!(
+                "Error creating output directory {}: {}",
+                options.outdir.display(),
+                e
+            );
+            e
+        })?;
+    }
+
+    if options.streaming {
+        let relationships = select_relationships(&options.only)?;
+        if options.inputs.len() > 1 {
+            return Err("--streaming reads a single input file".into());
+        }
+        if options.missing == MissingPolicy::Impute {
+            return Err(
+                "--missing impute needs two passes and cannot be used with --streaming".into(),
+            );
+        }
+        let reader = open_input(&options.inputs[0], options.delimiter)?;
+        let columns = ColumnConfig {
+            group: None,
+            ..options.column_config()
+        };
+        let fits = regress_streaming(reader, &columns, &relationships)?;
+        for (relationship, fit) in relationships.iter().zip(fits) {
+            let title = relationship.name;
+            match fit {
+                Some((slope, intercept)) => println!(
+                    "{title} Regression: y = {}x + {}",
+                    options.number_format.format(slope, 2),
+                    options.number_format.format(intercept, 2)
+                ),
+                None => status!("{title}: cannot fit regression: no variance in x"),
+            }
+        }
+        return Ok(None);
+    }
+
+    let columns = options.column_config();
+    let progress_every = log_enabled(LogLevel::Normal).then_some(options.progress_every);
+    let parsed = if let [input] = options.inputs.as_slice() {
+        let source: Box<dyn DataSource> = Box::new(CsvSource {
+            path: input.clone(),
+            delimiter: options.delimiter,
+            columns,
+            skip_malformed: SKIP_MALFORMED_ROWS,
+            progress_every,
+        });
+        source.records()
+    } else {
+        parse_artist_files(
+            &options.inputs,
+            options.delimiter,
+            &columns,
+            SKIP_MALFORMED_ROWS,
+            progress_every,
+        )
+        .map(|files| {
+            let mut merged = ParsedData {
+                records: Vec::new(),
+                skipped: 0,
+                negative_rows: 0,
+                missing: [0; 4],
+            };
+            for (input, parsed) in files {
+                info!(
+                    "{}: {} records, {} malformed rows.",
+                    input,
+                    parsed.records.len(),
+                    parsed.skipped
+                );
+                merged.records.extend(parsed.records);
+                merged.skipped += parsed.skipped;
+                merged.negative_rows += parsed.negative_rows;
+                for (total, count) in merged.missing.iter_mut().zip(parsed.missing) {
+                    *total += count;
+                }
+            }
+            merged
+        })
+    }
+    .map_err(|e| {
+        eprintln!("Error parsing dataset: {}", e);
+        e
+    })?;
+    info!(
+        "Parsed {} records, skipped {} malformed rows.",
+        parsed.records.len(),
+        parsed.skipped
+    );
+    if parsed.missing.iter().any(|count| *count > 0) {
+        let counts: Vec<String> = VARIABLE_NAMES
+            .iter()
+            .zip(parsed.missing)
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        let action = match options.missing {
+            MissingPolicy::Skip => "rows skipped",
+            MissingPolicy::Impute => "imputed with column means",
+        };
+        info!("Missing values: {} ({}).", counts.join(", "), action);
+    }
+    if parsed.negative_rows > 0 {
+        let action = match options.negatives {
+            NegativePolicy::Keep => "kept as-is; pass --negatives reject or clamp to change this",
+            NegativePolicy::Reject => "rejected",
+            NegativePolicy::Clamp => "clamped to zero",
+        };
+        status!(
+            "Warning: {} rows contain negative stream values ({})",
+            parsed.negative_rows,
+            action
+        );
+    }
+    let (parsed_count, skipped) = (parsed.records.len(), parsed.skipped);
+    if options.validate {
+        if let Some(limit) = options.max_skipped {
+            if skipped > limit {
+                return Err(format!(
+                    "validation failed: {} malformed rows exceed the limit of {}",
+                    skipped, limit
+                )
+                .into());
+            }
+        }
+    }
+    let mut data = parsed.records;
+    if data.is_empty() {
+        println!("No data to analyze.");
+        return Ok(None);
+    }
+
+    if let Some(min_streams) = options.min_streams {
+        let before = data.len();
+        data = filter_min_streams(data, min_streams);
+        info!(
+            "Dropped {} artists with fewer than {} total streams ({} remain).",
+            before - data.len(),
+            min_streams,
+            data.len()
+        );
+        if data.is_empty() {
+            println!("No artists reach the stream threshold; nothing to analyze.");
+            return Ok(None);
+        }
+    }
+
+    if let Some(strategy) = options.dedup {
+        let before = data.len();
+        data = deduplicate(data, strategy);
+        info!(
+            "Collapsed {} duplicate artist rows ({} artists remain).",
+            before - data.len(),
+            data.len()
+        );
+    }
 
-    for (title, mapper) in relationships {
-        let relationship_data: Vec<(f64, f64)> = data.iter().map(mapper).collect();
-        let (slope, intercept) = calculate_regression(&relationship_data);
-        println!("{title} Regression: y = {slope:.2}x + {intercept:.2}");
-        let file_name = format!("{}.png", title.replace(' ', "_").to_lowercase());
+    if let Some(n) = options.sample {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        data = sample_records(data, n, seed);
+        info!(
+            "Sampled {} records with seed {} (pass --seed {} to reproduce).",
+            data.len(),
+            seed,
+            seed
+        );
+    }
+
+    if let Some(filter) = &options.filter {
+        data = filter_by_name(&data, filter).into_iter().cloned().collect();
+        if data.is_empty() {
+            println!("No artists match filter '{}'; nothing to analyze.", filter);
+            return Ok(None);
+        }
+        info!("{} artists match filter '{}'.", data.len(), filter);
+    }
+
+    if let Some(n) = options.top_n {
+        data = top_n_by_total(data, n);
+        match data.last() {
+            Some(cutoff) => info!(
+                "Keeping the top {} artists, with at least {:.2} total streams.",
+                data.len(),
+                cutoff.total_streams
+            ),
+            None => {
+                println!("--top-n 0 leaves no artists; nothing to analyze.");
+                return Ok(None);
+            }
+        }
+    }
+
+    if let Some(x) = options.predict {
+        let relationship =
+            select_relationships(std::slice::from_ref(&options.relationship))?.remove(0);
+        let points: Vec<(f64, f64)> = data.iter().map(relationship.extract).collect();
+        let (slope, intercept) =
+            calculate_regression(&points).map_err(|e| format!("{}: {}", relationship.name, e))?;
+        println!(
+            "Predicted {} for {} = {}: {}",
+            relationship.y_label,
+            relationship.x_label,
+            x,
+            options
+                .number_format
+                .format(predict(slope, intercept, x), 2)
+        );
+        return Ok(None);
+    }
+
+    let total_streams: Vec<f64> = data.iter().map(|d| d.total_streams).collect();
+    let solo_streams: Vec<f64> = data.iter().map(|d| d.solo_streams).collect();
+    let feature_streams: Vec<f64> = data.iter().map(|d| d.feature_streams).collect();
+    let lead_streams: Vec<f64> = data.iter().map(|d| d.lead_streams).collect();
+    print_summary_table(&[
+        ("Total Streams", compute_summary(&total_streams)),
+        ("Solo Streams", compute_summary(&solo_streams)),
+        ("Featured Streams", compute_summary(&feature_streams)),
+        ("Lead Streams", compute_summary(&lead_streams)),
+    ]);
+    for (name, values) in [
+        ("Total Streams", &total_streams),
+        ("Solo Streams", &solo_streams),
+        ("Featured Streams", &feature_streams),
+        ("Lead Streams", &lead_streams),
+    ] {
+        let winsorized = winsorize(values, TRIM_PROPORTION)?;
+        println!(
+            "{name}: {}% trimmed mean = {:.2}, winsorized mean = {:.2}",
+            TRIM_PROPORTION * 100.0,
+            trimmed_mean(values, TRIM_PROPORTION)?,
+            winsorized.iter().sum::<f64>() / winsorized.len() as f64
+        );
+    }
+
+    if options.validate {
+        println!("Validation passed.");
+        return Ok(None);
+    }
+    print_matrix("Covariance matrix", &covariance_matrix(&data), 2);
+    let correlations = correlation_matrix(&data);
+    print_matrix("Correlation matrix", &correlations, 4);
+    let heatmap_file = options.output_path("correlation_heatmap.png");
+    visualize_correlation_heatmap(
+        &correlations,
+        &VARIABLE_NAMES,
+        &options.style,
+        &heatmap_file.to_string_lossy(),
+    )
+    .map_err(|e| {
+        eprintln!("Error generating correlation heatmap: {e}");
+        e
+    })?;
+    println!(
+        "Total Streams percentiles: p50 = {:.2}, p90 = {:.2}, p99 = {:.2}",
+        percentile(&total_streams, 50.0)?,
+        percentile(&total_streams, 90.0)?,
+        percentile(&total_streams, 99.0)?
+    );
+
+    let breakdown = stream_breakdown(&data);
+    println!(
+        "Share of total streams: solo {:.1}%, featured {:.1}%, lead {:.1}%",
+        breakdown.solo_percent, breakdown.feature_percent, breakdown.lead_percent
+    );
+
+    let inconsistent = inconsistent_artists(&data, CONSISTENCY_TOLERANCE);
+    if !inconsistent.is_empty() {
+        let names: Vec<&str> = inconsistent
+            .iter()
+            .take(LISTED_INCONSISTENCIES)
+            .map(|d| d.name.as_str())
+            .collect();
+        status!(
+            "Warning: {} artists have solo + featured + lead streams differing from total streams by more than {}% (e.g. {})",
+            inconsistent.len(),
+            CONSISTENCY_TOLERANCE * 100.0,
+            names.join(", ")
+        );
+    }
+
+    let histogram_file = options.output_path("total_streams_distribution.png");
+    visualize_histogram(
+        &total_streams,
+        HISTOGRAM_BINS,
+        "Total Streams Distribution",
+        &options.style,
+        &histogram_file.to_string_lossy(),
+    )
+    .map_err(|e| {
+        eprintln!("Error generating histogram: {e}");
+        e
+    })?;
+
+    let boxplot_file = options.output_path("stream_distribution_boxplot.png");
+    visualize_boxplot(
+        &[
+            ("Solo Streams", solo_streams),
+            ("Featured Streams", feature_streams),
+            ("Lead Streams", lead_streams),
+        ],
+        &options.style,
+        &boxplot_file.to_string_lossy(),
+    )
+    .map_err(|e| {
+        eprintln!("Error generating box plot: {e}");
+        e
+    })?;
+
+    let mut results = Vec::new();
+    let mut combined = Vec::new();
+    let mut fit_quality = Vec::new();
+    let mut effect_sizes = Vec::new();
+    for relationship in select_relationships(&options.only)? {
+        if let Some((result, points)) = analyze_relationship(&relationship, &data, None, options)? {
+            combined.push((relationship.name, points, result.slope, result.intercept));
+            fit_quality.push((relationship.key, result.r_squared));
+            if let Some(beta) = result.standardized_slope {
+                effect_sizes.push((relationship.x_label, beta));
+            }
+            results.push(result);
+        }
+    }
+    if effect_sizes.len() > 1 {
+        effect_sizes.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        let ranking: Vec<String> = effect_sizes
+            .iter()
+            .map(|(name, beta)| format!("{} ({})", name, options.number_format.format(*beta, 4)))
+            .collect();
+        println!("Predictors by standardized β: {}", ranking.join(", "));
+    }
+
+    if options.group_column.is_some() {
+        for (group, members) in group_by(data.clone()) {
+            println!("Group '{}': {} artists", group, members.len());
+            for relationship in select_relationships(&options.only)? {
+                if let Some((result, _)) =
+                    analyze_relationship(&relationship, &members, Some(&group), options)?
+                {
+                    results.push(result);
+                }
+            }
+        }
+    }
+
+    let position = |key: &str| fit_quality.iter().position(|(k, _)| *k == key);
+    if let (Some(solo), Some(feature)) = (position("solo"), position("feature")) {
+        match compare_slopes(&combined[solo].1, &combined[feature].1) {
+            Ok(comparison) => println!(
+                "Solo vs Featured slope difference: {} (SE {}, z = {:.2}, {})",
+                options.number_format.format(comparison.difference, 4),
+                options.number_format.format(comparison.standard_error, 4),
+                comparison.z,
+                format_p_value(comparison.p_value)
+            ),
+            Err(e) => status!("Cannot compare solo and featured slopes: {e}"),
+        }
+    }
 
-        if let Err(e) = visualize_relationship(&relationship_data, slope, intercept, title, &file_name) {
-            eprintln!("Error generating plot for {title}: {e}");
+    let combined_file = options.output_path("total_streams_vs_all_predictors.png");
+    visualize_combined(&combined, &options.style, &combined_file.to_string_lossy()).map_err(
+        |e| {
+            eprintln!("Error generating combined plot: {e}");
+            e
+        },
+    )?;
+
+    let slopes: Vec<(&str, f64)> = combined
+        .iter()
+        .map(|(name, _, slope, _)| (*name, *slope))
+        .collect();
+    let slopes_file = options.output_path("relationship_slopes.png");
+    visualize_slope_comparison(&slopes, &options.style, &slopes_file.to_string_lossy()).map_err(
+        |e| {
+            eprintln!("Error generating slope comparison: {e}");
+            e
+        },
+    )?;
+
+    let report_file = options.output_path("regression_report.csv");
+    write_report(&report_file.to_string_lossy(), &results).map_err(|e| {
+        eprintln!("Error writing regression report: {e}");
+        e
+    })?;
+
+    let json_file = options.output_path("analysis.json");
+    write_json(&json_file.to_string_lossy(), &data, &results).map_err(|e| {
+        eprintln!("Error writing JSON export: {e}");
+        e
+    })?;
+
+    match multiple_regression(&data) {
+        Ok(coefficients) => {
+            let r_squared = multiple_r_squared(&data, &coefficients);
+            let num = |value: f64, default_precision: usize| {
+                options.number_format.format(value, default_precision)
+            };
+            println!(
+                "Multiple Regression: Total = {} + {} * Solo + {} * Featured + {} * Lead, R² = {}, adjusted R² = {}",
+                num(coefficients[0], 2),
+                num(coefficients[1], 4),
+                num(coefficients[2], 4),
+                num(coefficients[3], 4),
+                num(r_squared, 4),
+                num(adjusted_r_squared(r_squared, data.len(), coefficients.len() - 1), 4)
+            );
         }
+        Err(e) => status!("Warning: cannot fit multiple regression: {e}"),
     }
+    if let Some(lambda) = options.ridge {
+        match ridge_regression(&data, lambda) {
+            Ok(coefficients) => {
+                let r_squared = multiple_r_squared(&data, &coefficients);
+                let num = |value: f64, default_precision: usize| {
+                    options.number_format.format(value, default_precision)
+                };
+                println!(
+                    "Ridge Regression (λ = {}): Total = {} + {} * Solo + {} * Featured + {} * Lead, R² = {}",
+                    lambda,
+                    num(coefficients[0], 2),
+                    num(coefficients[1], 4),
+                    num(coefficients[2], 4),
+                    num(coefficients[3], 4),
+                    num(r_squared, 4)
+                );
+            }
+            Err(e) => status!("Warning: cannot fit ridge regression: {e}"),
+        }
+    }
+
+    println!("{}", format_summary(parsed_count, skipped, &fit_quality));
+    Ok(Some(RunSummary {
+        records: parsed_count,
+        skipped,
+        r_squared: fit_quality,
+    }))
+}
+
+/// Runs the full analysis on every `*.csv` file in `dir`, writing each file's outputs to a
+/// subdirectory of the output directory named after it, then a combined `batch_summary.csv`.
+/// Files whose names map to the same subdirectory get a numeric suffix. A file that fails
+/// is reported and skipped without stopping the batch.
+fn run_batch(options: &CliOptions, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("cannot read batch directory {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "csv"))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("no .csv files found in {}", dir.display()).into());
+    }
+
+    let mut rows = Vec::new();
+    let mut subdirs = HashSet::new();
+    for file in &files {
+        let name = file.display().to_string();
+        info!("Processing {}", name);
+        let stem = file_stem_part(&file.file_stem().unwrap_or_default().to_string_lossy());
+        let mut subdir = stem.clone();
+        for suffix in 2.. {
+            if subdirs.insert(subdir.clone()) {
+                break;
+            }
+            subdir = format!("{}_{}", stem, suffix);
+        }
+        if subdir != stem {
+            status!(
+                "Warning: {} shares the output directory {}/ with an earlier file; writing to {}/ instead",
+                name,
+                stem,
+                subdir
+            );
+        }
+        let file_options = CliOptions {
+            inputs: vec![file.to_string_lossy().into_owned()],
+            outdir: options.outdir.join(&subdir),
+            batch: None,
+            ..options.clone()
+        };
+        let outcome = run(&file_options).map_err(|e| {
+            eprintln!("Error processing {}: {}", name, e);
+            e.to_string()
+        });
+        rows.push((name, outcome));
+    }
+
+    fs::create_dir_all(&options.outdir)?;
+    let keys: Vec<&str> = select_relationships(&options.only)?
+        .iter()
+        .map(|relationship| relationship.key)
+        .collect();
+    let summary_file = options.output_path("batch_summary.csv");
+    write_batch_summary(&summary_file.to_string_lossy(), &keys, &rows)?;
+    let failed = rows.iter().filter(|(_, outcome)| outcome.is_err()).count();
+    let no_results = rows
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, Ok(None)))
+        .count();
+    println!(
+        "Batch complete: {} of {} files succeeded, {} produced no results, {} failed.",
+        files.len() - failed - no_results,
+        files.len(),
+        no_results,
+        failed
+    );
+    Ok(())
 }
 
-///tests for the program as part of the rubric
 #[cfg(test)]
 mod tests {
     use super::*;
+    use finalproject::MeshConfig;
+
+    #[test]
+    fn test_format_summary() {
+        assert_eq!(
+            format_summary(1234, 5, &[("solo", 0.81), ("feature", 0.4404)]),
+            "SUMMARY records=1234 skipped=5 solo_r2=0.8100 feature_r2=0.4404"
+        );
+        assert_eq!(format_summary(0, 0, &[]), "SUMMARY records=0 skipped=0");
+    }
+
+    #[test]
+    fn test_run_with_header_only_input() {
+        let input = std::env::temp_dir().join("finalproject_test_header_only.csv");
+        fs::write(&input, "Artist,Streams,Daily,As lead,Solo,As feature\n").unwrap();
+        let outdir = std::env::temp_dir().join("finalproject_test_header_only_plots");
+        let _ = fs::remove_dir_all(&outdir);
+
+        let options = CliOptions {
+            inputs: vec![input.to_string_lossy().into_owned()],
+            outdir: outdir.clone(),
+            ..CliOptions::default()
+        };
+        run(&options).unwrap();
+        assert_eq!(fs::read_dir(&outdir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_validate_all_malformed_input() {
+        let input = std::env::temp_dir().join("finalproject_test_all_malformed.csv");
+        fs::write(
+            &input,
+            "Artist,Streams,Daily,As lead,Solo,As feature\nA,x,1,2,3,4\nB,1,1,y,3,4\n",
+        )
+        .unwrap();
+
+        let options = CliOptions {
+            inputs: vec![input.to_string_lossy().into_owned()],
+            validate: true,
+            max_skipped: Some(0),
+            log_level: LogLevel::Quiet,
+            ..CliOptions::default()
+        };
+        let err = run(&options).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("2 malformed rows exceed the limit of 0"));
+        let options = CliOptions {
+            max_skipped: Some(2),
+            ..options
+        };
+        assert!(run(&options).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let dir = std::env::temp_dir().join("finalproject_test_batch");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut good = String::from("Artist,Streams,Daily,As lead,Solo,As feature\n");
+        for i in 1..=8 {
+            let (lead, feature) = (i * 10 + i % 3, i * 4 + i % 2);
+            good.push_str(&format!(
+                "A{i},{},1,{lead},{},{feature}\n",
+                lead + feature,
+                i * 7
+            ));
+        }
+        fs::write(dir.join("My-Data.csv"), &good).unwrap();
+        fs::write(dir.join("my_data.csv"), &good).unwrap();
+        fs::write(dir.join("bad.csv"), "Name,Plays\nDrake,1\n").unwrap();
+        fs::write(
+            dir.join("empty.csv"),
+            "Artist,Streams,Daily,As lead,Solo,As feature\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a dataset").unwrap();
+        let outdir = std::env::temp_dir().join("finalproject_test_batch_plots");
+        let _ = fs::remove_dir_all(&outdir);
+
+        let options = CliOptions {
+            outdir: outdir.clone(),
+            log_level: LogLevel::Quiet,
+            ..CliOptions::default()
+        };
+        run_batch(&options, &dir).unwrap();
+        let summary = fs::read_to_string(outdir.join("batch_summary.csv")).unwrap();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("file,status,records,skipped,solo_r2"));
+        assert!(lines[1].contains("My-Data.csv,ok,8,0,"));
+        assert!(lines[2].contains("bad.csv,failed,"));
+        assert!(lines[3].contains("empty.csv,no_results,"));
+        assert!(lines[4].contains("my_data.csv,ok,8,0,"));
+        for subdir in ["my_data", "my_data_2"] {
+            assert!(outdir.join(subdir).join("regression_report.csv").is_file());
+        }
+
+        assert!(run_batch(&options, &outdir.join("my_data").join("missing")).is_err());
+        let args = ["--batch", "dir", "--input", "a.csv"].map(String::from);
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn test_run_list_columns() {
+        let input = std::env::temp_dir().join("finalproject_test_list_columns.csv");
+        fs::write(&input, "Artist,Streams\nDrake,1\n").unwrap();
+        let outdir = std::env::temp_dir().join("finalproject_test_list_columns_plots");
+        let _ = fs::remove_dir_all(&outdir);
+
+        let args = ["--list-columns".to_string()];
+        let options = CliOptions {
+            inputs: vec![input.to_string_lossy().into_owned()],
+            outdir: outdir.clone(),
+            ..parse_args(args).unwrap().unwrap()
+        };
+        run(&options).unwrap();
+        assert!(!outdir.exists());
+    }
+
+    /// Parses `args`, which must succeed without asking for help.
+    fn parse(args: &[&str]) -> CliOptions {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+            .unwrap()
+            .unwrap()
+    }
 
     #[test]
-    fn test_parse_artist_data() {
-        let test_csv = "Name,Total Streams,Solo Streams,Lead Streams,Feature Streams\n\
-                        Artist1,1000,500,300,200\n\
-                        Artist2,2000,800,600,400\n";
-        let mut rdr = csv::Reader::from_reader(test_csv.as_bytes());
-        let data = parse_artist_data("artists.csv").unwrap();
-        assert_eq!(data.len(), 2);
+    fn test_parse_args_input_output() {
         assert_eq!(
-            data[0],
-            ArtistData {
-                total_streams: 1000.0,
-                solo_streams: 500.0,
-                feature_streams: 200.0,
-                lead_streams: 300.0
+            parse(&["--input", "mydata.csv", "--outdir", "./plots"]),
+            CliOptions {
+                inputs: vec!["mydata.csv".to_string()],
+                outdir: PathBuf::from("./plots"),
+                ..CliOptions::default()
             }
         );
+        assert_eq!(parse_args(Vec::new()).unwrap(), Some(CliOptions::default()));
+        assert_eq!(parse_args(["--help".to_string()]).unwrap(), None);
+        assert_eq!(
+            parse(&["--input", "2021.csv", "--input", "2022.csv"]).inputs,
+            ["2021.csv", "2022.csv"]
+        );
+
+        let options = parse(&["--outdir", "out", "--output-prefix", "dataset1"]);
+        assert_eq!(
+            options.output_path("solo_relationship.png"),
+            Path::new("out").join("dataset1_solo_relationship.png")
+        );
+        assert_eq!(
+            CliOptions::default().output_path("analysis.json"),
+            Path::new(".").join("analysis.json")
+        );
+        assert_eq!(file_stem_part("Hip Hop/R&B"), "hip_hop_r_b");
+
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert!(parse_delimiter(";;").is_err());
+        assert_eq!(
+            parse(&["--comma-style", "comma-as-decimal"]).comma_style,
+            CommaStyle::Decimal
+        );
+        assert_eq!(
+            parse(&["--negatives", "clamp"]).negatives,
+            NegativePolicy::Clamp
+        );
+        assert_eq!(
+            parse(&["--missing", "impute"]).missing,
+            MissingPolicy::Impute
+        );
+
+        let columns = parse(&["--solo", "Solo", "--lead", "As lead"]).column_config();
+        let headers =
+            csv::StringRecord::from(vec!["Artist", "Streams", "Daily", "As lead", "Solo"]);
+        assert_eq!(columns.solo.resolve(&headers).unwrap(), 4);
+        assert_eq!(columns.lead.resolve(&headers).unwrap(), 3);
+        assert_eq!(columns.total.resolve(&headers).unwrap(), 1);
+        assert_eq!(
+            parse(&["--group-column", "Genre"]).group_column.as_deref(),
+            Some("Genre")
+        );
     }
 
     #[test]
-    fn test_calculate_regression() {
-        let data = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
-        let (slope, intercept) = calculate_regression(&data);
-        assert!((slope - 2.0).abs() < 1e-6);
-        assert!((intercept - 0.0).abs() < 1e-6);
+    fn test_parse_args_plot_style() {
+        let style = parse(&["--point-radius", "2", "--point-alpha", "0.3"]).style;
+        assert_eq!((style.point_radius, style.point_alpha), (2, 0.3));
+        assert_eq!(parse(&["--line-samples", "50"]).style.line_samples, 50);
+        assert_eq!(
+            parse(&["--no-minor-grid", "--x-labels", "5"]).style.mesh,
+            MeshConfig {
+                x_labels: 5,
+                y_labels: 11,
+                minor_grid: false,
+            }
+        );
+        assert_eq!(
+            parse(&["--colors", "lead=black,#ff8000"])
+                .style
+                .palette
+                .colors("lead"),
+            SeriesColors {
+                point: parse_color("black").unwrap(),
+                line: parse_color("#ff8000").unwrap(),
+            }
+        );
+        assert_eq!(parse(&["--hexbin", "30"]).hexbin, Some(30));
+        assert!(parse(&["--label-points"]).label_points);
+        assert_eq!(parse(&["--stats-corner", "none"]).stats_corner, None);
+
+        let options = parse(&["--title", "lead={x} = {y}?", "--title", "{x} vs {y}"]);
+        assert_eq!(options.title.as_deref(), Some("{x} vs {y}"));
+        assert_eq!(
+            options.relationship_titles,
+            [("lead".to_string(), "{x} = {y}?".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_statistics() {
+        let options = parse(&["--validate", "--max-skipped", "5"]);
+        assert!(options.validate);
+        assert_eq!(options.max_skipped, Some(5));
+        assert_eq!(parse(&["--plots", "solo,lead"]).only, ["solo", "lead"]);
+        assert_eq!(
+            parse(&["--predictions", "feature"]).predictions.as_deref(),
+            Some("feature")
+        );
+        let options = parse(&["--sample", "100", "--seed", "7"]);
+        assert_eq!((options.sample, options.seed), (Some(100), Some(7)));
+        assert_eq!(parse(&["--ridge", "2.5"]).ridge, Some(2.5));
+        assert_eq!(parse(&["--min-streams", "2.5"]).min_streams, Some(2.5));
+
+        let number_format = parse(&["--precision", "6", "--scientific"]).number_format;
+        assert_eq!(number_format.format(1234.5, 2), "1.234500e3");
+        assert_eq!(NumberFormat::default().format(0.123456, 4), "0.1235");
+    }
+
+    #[test]
+    fn test_parse_args_log_level() {
+        assert_eq!(parse(&["-q"]).log_level, LogLevel::Quiet);
+        assert_eq!(parse(&["--verbose"]).log_level, LogLevel::Verbose);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let invalid: &[&[&str]] = &[
+            &["--input"],
+            &["--bogus"],
+            &["--progress-every", "0"],
+            &["--output-prefix", "a/b"],
+            &["--comma-style", "dot"],
+            &["--negatives", "drop"],
+            &["--point-alpha", "1.5"],
+            &["--line-samples", "0"],
+            &["--colors", "total=red,blue"],
+            &["--colors", "solo=red"],
+            &["--hexbin", "0"],
+            &["--stats-corner", "middle"],
+            &["--only", "total"],
+            &["--predictions", "total"],
+            &["--seed", "-1"],
+            &["--moving-average", "0"],
+            &["--ridge", "-1"],
+            &["--min-streams", "NaN"],
+            &["--precision", "x"],
+        ];
+        for args in invalid {
+            assert!(
+                parse_args(args.iter().map(|arg| arg.to_string())).is_err(),
+                "{:?} should be rejected",
+                args
+            );
+        }
     }
 }